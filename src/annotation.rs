@@ -0,0 +1,137 @@
+use destination::Fit;
+use serialize::write_literal_string;
+use std::io::{Result, Write};
+
+/// A link annotation on a page, as described in section 12.5.6.5 of the PDF
+/// specification.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Link {
+    rect: [f32; 4],
+    target: LinkTarget,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum LinkTarget {
+    Uri(String),
+    /// A page of the document by index.
+    Page(usize, Fit),
+    /// A named destination.
+    Named(String),
+}
+
+impl Link {
+    pub fn uri(rect: [f32; 4], uri: &str) -> Link {
+        Link {
+            rect,
+            target: LinkTarget::Uri(uri.to_string()),
+        }
+    }
+
+    pub fn page(rect: [f32; 4], page_index: usize, fit: Fit) -> Link {
+        Link {
+            rect,
+            target: LinkTarget::Page(page_index, fit),
+        }
+    }
+
+    pub fn named(rect: [f32; 4], name: &str) -> Link {
+        Link {
+            rect,
+            target: LinkTarget::Named(name.to_string()),
+        }
+    }
+
+    /// The index of the page the link points to, if any.
+    pub fn page_index(&self) -> Option<usize> {
+        match self.target {
+            LinkTarget::Page(index, _) => Some(index),
+            LinkTarget::Uri(_) | LinkTarget::Named(_) => None,
+        }
+    }
+
+    /// The name of the destination the link points to, if any.
+    pub fn destination_name(&self) -> Option<&str> {
+        match self.target {
+            LinkTarget::Named(ref name) => Some(name),
+            LinkTarget::Uri(_) | LinkTarget::Page(..) => None,
+        }
+    }
+
+    /// Write the annotation dictionary. `page_id` is the object id of the
+    /// page the link points to.
+    pub fn write_dictionary(
+        &self,
+        output: &mut dyn Write,
+        page_id: Option<usize>,
+    ) -> Result<()> {
+        let r = self.rect;
+        writeln!(
+            output,
+            "<< /Type /Annot /Subtype /Link\n   \
+             /Rect [{} {} {} {}] /Border [0 0 0]",
+            r[0], r[1], r[2], r[3]
+        )?;
+        match self.target {
+            LinkTarget::Uri(ref uri) => {
+                write!(output, "   /A << /S /URI /URI (")?;
+                output.write_all(&escape_uri(uri))?;
+                writeln!(output, ") >>")?;
+            }
+            LinkTarget::Page(_, fit) => {
+                if let Some(page_id) = page_id {
+                    writeln!(output, "   /Dest [{} 0 R {}]", page_id, fit)?;
+                }
+            }
+            LinkTarget::Named(ref name) => {
+                write!(output, "   /Dest ")?;
+                write_literal_string(output, name.as_bytes())?;
+                writeln!(output)?;
+            }
+        }
+        writeln!(output, ">>")
+    }
+}
+
+/// Return a URI as content of a string. URIs are 7 bit ASCII, so other
+/// characters are percent-encoded as UTF-8, and the delimiters of strings
+/// are escaped.
+fn escape_uri(uri: &str) -> Vec<u8> {
+    let mut result = Vec::with_capacity(uri.len());
+    for &byte in uri.as_bytes() {
+        match byte {
+            b'(' | b')' | b'\\' => result.extend_from_slice(&[b'\\', byte]),
+            0x21..=0x7E => result.push(byte),
+            _ => result.extend_from_slice(format!("%{:02X}", byte).as_bytes()),
+        }
+    }
+    result
+}
+
+#[test]
+fn test_link_dictionary() {
+    let mut output = Vec::new();
+    Link::uri([0.0, 0.0, 10.0, 5.0], "https://example.com/a b(ü)")
+        .write_dictionary(&mut output, None)
+        .unwrap();
+    Link::page([0.0, 0.0, 10.0, 5.0], 3, Fit::page())
+        .write_dictionary(&mut output, Some(12))
+        .unwrap();
+    Link::named([0.0, 0.0, 10.0, 5.0], "section-3")
+        .write_dictionary(&mut output, None)
+        .unwrap();
+    assert_eq!(
+        "<< /Type /Annot /Subtype /Link\n   \
+         /Rect [0 0 10 5] /Border [0 0 0]\n   \
+         /A << /S /URI /URI (https://example.com/a%20b\\(%C3%BC\\)) >>\n\
+         >>\n\
+         << /Type /Annot /Subtype /Link\n   \
+         /Rect [0 0 10 5] /Border [0 0 0]\n   \
+         /Dest [12 0 R /Fit]\n\
+         >>\n\
+         << /Type /Annot /Subtype /Link\n   \
+         /Rect [0 0 10 5] /Border [0 0 0]\n   \
+         /Dest (section-3)\n\
+         >>\n",
+        String::from_utf8(output).unwrap()
+    );
+}
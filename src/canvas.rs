@@ -1,35 +1,52 @@
+use annotation::Link;
+use destination::Fit;
 use fontref::FontRef;
 use fontsource::{Font, FontSource};
-use graphicsstate::{CapStyle, Color, JoinStyle, Matrix};
-use outline::OutlineItem;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufWriter, Result, Write};
+use geometry::{Path, Point, Rect, Segment, StrokeStyle};
+use graphicsstate::{
+    write_color, BlendMode, CapStyle, Color, ExtGState, JoinStyle, Matrix,
+    Shading, SoftMaskType, TransparencyGroup,
+};
+use image::Image;
+use outline::{OutlineId, OutlineItem};
+use resources::Resources;
+use std::io::{Error, ErrorKind, Result, Write};
 use std::sync::Arc;
 use textobject::TextObject;
 use units::{LengthUnit, Points, UserSpace};
+use xobject::Form;
+use Pdf;
 
 /// An visual area where content can be drawn (a page).
 ///
 /// Provides methods for defining and stroking or filling paths, as well as
 /// placing text objects.
 pub struct Canvas<'a> {
-    output: &'a mut BufWriter<File>,
-    fonts: &'a mut HashMap<Font, FontRef>,
-    outline_items: &'a mut Vec<OutlineItem>,
+    pdf: &'a mut Pdf,
+    output: &'a mut Vec<u8>,
+    resources: &'a mut Resources,
+    outline_items: &'a mut Vec<(OutlineId, OutlineItem)>,
+    links: &'a mut Vec<Link>,
+    destinations: &'a mut Vec<(String, Fit)>,
 }
 
 impl<'a> Canvas<'a> {
     // Should not be called by user code.
     pub(crate) fn new(
-        output: &'a mut BufWriter<File>,
-        fonts: &'a mut HashMap<Font, FontRef>,
-        outline_items: &'a mut Vec<OutlineItem>,
+        pdf: &'a mut Pdf,
+        output: &'a mut Vec<u8>,
+        resources: &'a mut Resources,
+        outline_items: &'a mut Vec<(OutlineId, OutlineItem)>,
+        links: &'a mut Vec<Link>,
+        destinations: &'a mut Vec<(String, Fit)>,
     ) -> Canvas<'a> {
         Canvas {
+            pdf,
             output,
-            fonts,
+            resources,
             outline_items,
+            links,
+            destinations,
         }
     }
     /// Append a closed rectangle with a corner at (x, y) and extending width ×
@@ -43,6 +60,10 @@ impl<'a> Canvas<'a> {
     ) -> Result<()> {
         writeln!(self.output, "{} {} {} {} re", x, y, width, height)
     }
+    /// Append the closed rectangle `rect` to the current path.
+    pub fn rect<T: LengthUnit>(&mut self, rect: &Rect<T>) -> Result<()> {
+        self.rectangle(rect.min_x(), rect.min_y(), rect.width(), rect.height())
+    }
     /// Set the line join style in the graphics state.
     pub fn set_line_join_style(&mut self, style: JoinStyle) -> Result<()> {
         writeln!(self.output, "{} j", style)
@@ -58,6 +79,16 @@ impl<'a> Canvas<'a> {
     ) -> Result<()> {
         writeln!(self.output, "{} w", width)
     }
+    /// Set the miter limit in the graphics state, the ratio of the length
+    /// of a miter to the line width above which mitered joins are beveled.
+    /// The limit must be at least 1.  If it is not the operation is not
+    /// executed.
+    pub fn set_miter_limit(&mut self, limit: f32) -> Result<()> {
+        if limit.is_nan() || limit < 1.0 {
+            return Ok(());
+        }
+        writeln!(self.output, "{} M", limit)
+    }
     /// Set the line dash pattern in the graphics state. Values must not be
     /// negative, or all array values must not be 0. If this happens the
     /// operation is not executed.
@@ -85,19 +116,278 @@ impl<'a> Canvas<'a> {
         }
         writeln!(self.output, "] {} d", phase)
     }
+    /// Set the line width, join and cap styles, miter limit and dash
+    /// pattern of `style` in the graphics state, so that stroking a path
+    /// paints the area of its `Path::stroke_outline`.
+    pub fn set_stroke_style<T: LengthUnit>(
+        &mut self,
+        style: &StrokeStyle<T>,
+    ) -> Result<()> {
+        self.set_line_width(style.line_width())?;
+        self.set_line_join_style(style.line_join_style())?;
+        self.set_line_cap_style(style.line_cap_style())?;
+        self.set_miter_limit(style.miter_limit())?;
+        match style.dash() {
+            (&[], _) => writeln!(self.output, "[] 0 d"),
+            (pattern, phase) => self.set_dash(pattern, phase),
+        }
+    }
     /// Set color for stroking operations.
     pub fn set_stroke_color(&mut self, color: Color) -> Result<()> {
-        match color {
-            Color::RGB { .. } => writeln!(self.output, "{} SC", color),
-            Color::Gray { .. } => writeln!(self.output, "{} G", color),
-        }
+        write_color(self.output, self.resources, &color, true)
     }
     /// Set color for non-stroking operations.
     pub fn set_fill_color(&mut self, color: Color) -> Result<()> {
-        match color {
-            Color::RGB { .. } => writeln!(self.output, "{} sc", color),
-            Color::Gray { .. } => writeln!(self.output, "{} g", color),
+        write_color(self.output, self.resources, &color, false)
+    }
+    /// Set the constant opacity for non-stroking operations, from 0
+    /// (transparent) to 1 (opaque). Returns an error of kind `InvalidInput`
+    /// if `alpha` is NaN.
+    pub fn set_fill_alpha(&mut self, alpha: f32) -> Result<()> {
+        check_alpha(alpha)?;
+        self.set_ext_gstate(&ExtGState::FillAlpha(alpha))
+    }
+    /// Set the constant opacity for stroking operations, from 0
+    /// (transparent) to 1 (opaque). Returns an error of kind `InvalidInput`
+    /// if `alpha` is NaN.
+    pub fn set_stroke_alpha(&mut self, alpha: f32) -> Result<()> {
+        check_alpha(alpha)?;
+        self.set_ext_gstate(&ExtGState::StrokeAlpha(alpha))
+    }
+    /// Set the blend mode for combining painted content with the content
+    /// beneath it.
+    pub fn set_blend_mode(&mut self, mode: BlendMode) -> Result<()> {
+        self.set_ext_gstate(&ExtGState::BlendMode(mode))
+    }
+    /// Set a soft mask that modulates the opacity of all following painting
+    /// operations until the graphics state is restored.
+    ///
+    /// The mask is drawn by the function `render_mask` within the bounding
+    /// box with a corner at (x, y) and extending width × height. Everything
+    /// outside of the bounding box is masked out.
+    pub fn set_soft_mask<F, T>(
+        &mut self,
+        mask: SoftMaskType,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        render_mask: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+        T: LengthUnit,
+    {
+        // The luminosity is computed in the color space of the group.
+        let group = "<< /Type /Group /S /Transparency /CS /DeviceRGB >>";
+        let bbox = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        let group_id = self.render_form(bbox, Some(group), render_mask)?;
+        self.set_ext_gstate(&ExtGState::SoftMask(Some((mask, group_id))))
+    }
+    /// Remove the soft mask set by [set_soft_mask](#method.set_soft_mask).
+    pub fn clear_soft_mask(&mut self) -> Result<()> {
+        self.set_ext_gstate(&ExtGState::SoftMask(None))
+    }
+    fn set_ext_gstate(&mut self, ext_gstate: &ExtGState) -> Result<()> {
+        let name = self.resources.ext_gstate_name(ext_gstate);
+        writeln!(self.output, "{} gs", name)
+    }
+
+    /// Draw content as a transparency group, which is composited on its own
+    /// and then painted as a whole with the current alpha and blend mode.
+    ///
+    /// The content is drawn by the function `render_group` and clipped to
+    /// the bounding box with a corner at (x, y) and extending width ×
+    /// height.
+    pub fn transparency_group<F, T>(
+        &mut self,
+        group: TransparencyGroup,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        render_group: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+        T: LengthUnit,
+    {
+        let group = group.to_string();
+        let bbox = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        let form_id = self.render_form(bbox, Some(&group), render_group)?;
+        let name = self.resources.xobject_name(form_id);
+        writeln!(self.output, "{} Do", name)
+    }
+
+    /// Place a form created with
+    /// [Pdf::create_form](struct.Pdf.html#method.create_form), transformed
+    /// by `matrix`.
+    pub fn draw_form(&mut self, form: Form, matrix: Matrix) -> Result<()> {
+        let name = self.resources.xobject_name(form.object_id);
+        writeln!(self.output, "q\n{} cm\n{} Do\nQ", matrix, name)
+    }
+    /// Draw an image, stretched to the rectangle with a corner at (x, y) and
+    /// extending width × height. Stencil masks are painted with the current
+    /// fill color.
+    pub fn draw_image<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Result<()> {
+        let object_id = self.pdf.image_object_id(image)?;
+        let name = self.resources.xobject_name(object_id);
+        // Images are painted into the unit square.
+        writeln!(
+            self.output,
+            "q\n{} 0 0 {} {} {} cm\n{} Do\nQ",
+            width, height, x, y, name
+        )
+    }
+    /// Draw an image stretched to `rect`.
+    pub fn draw_image_rect<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        rect: &Rect<T>,
+    ) -> Result<()> {
+        let (x, y) = (rect.min_x(), rect.min_y());
+        self.draw_image(image, x, y, rect.width(), rect.height())
+    }
+    /// Draw an image as inline image, stretched to the rectangle with a
+    /// corner at (x, y) and extending width × height.
+    ///
+    /// Inline images are part of the content stream, which saves an object
+    /// for small images that are drawn only once. Returns an error of kind
+    /// `InvalidInput` for images with masks, which can't be inline.
+    pub fn draw_inline_image<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Result<()> {
+        let mut inline = Vec::new();
+        image.write_inline(&mut inline)?;
+        if image.bits_per_component() == 16 {
+            self.pdf.require_version(5);
         }
+        writeln!(self.output, "q\n{} 0 0 {} {} {} cm", width, height, x, y)?;
+        self.output.extend_from_slice(&inline);
+        writeln!(self.output, "Q")
+    }
+    /// Draw an image as large as possible within the rectangle with a
+    /// corner at (x, y) and extending width × height, keeping its aspect
+    /// ratio. The image is centered in the rectangle.
+    pub fn draw_image_fit<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Result<()> {
+        let scale = (width.pt / image.width() as f32)
+            .min(height.pt / image.height() as f32);
+        self.draw_image_scaled(image, scale, x, y, width, height)
+    }
+    /// Cover the rectangle with a corner at (x, y) and extending width ×
+    /// height with an image, keeping its aspect ratio. The image is
+    /// centered in the rectangle and clipped to it.
+    pub fn draw_image_fill<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Result<()> {
+        let scale = (width.pt / image.width() as f32)
+            .max(height.pt / image.height() as f32);
+        writeln!(self.output, "q")?;
+        self.rectangle(x, y, width, height)?;
+        self.clip()?;
+        self.draw_image_scaled(image, scale, x, y, width, height)?;
+        writeln!(self.output, "Q")
+    }
+    /// Draw an image with its size in pixels times `scale`, centered in a
+    /// rectangle.
+    fn draw_image_scaled<T: LengthUnit>(
+        &mut self,
+        image: &Image,
+        scale: f32,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Result<()> {
+        let image_width = image.width() as f32 * scale;
+        let image_height = image.height() as f32 * scale;
+        self.draw_image(
+            image,
+            pt!((x.pt + (width.pt - image_width) / 2.0)),
+            pt!((y.pt + (height.pt - image_height) / 2.0)),
+            pt!(image_width),
+            pt!(image_height),
+        )
+    }
+    /// Render content with a nested canvas into a form XObject and return
+    /// its object id.
+    fn render_form<F>(
+        &mut self,
+        bbox: [f32; 4],
+        group: Option<&str>,
+        render_contents: F,
+    ) -> Result<usize>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+    {
+        let mut content = Vec::new();
+        let mut resources = Resources::default();
+        render_contents(&mut Canvas::new(
+            self.pdf,
+            &mut content,
+            &mut resources,
+            self.outline_items,
+            self.links,
+            self.destinations,
+        ))?;
+        self.pdf.write_form(bbox, group, &content, resources)
+    }
+    /// Set a shading as color for stroking operations.
+    ///
+    /// The coordinates of the shading refer to the default coordinate space
+    /// of the page and are not affected by [concat](#method.concat).
+    pub fn set_stroke_shading(&mut self, shading: &Shading) -> Result<()> {
+        let name = self.shading_pattern_name(shading)?;
+        writeln!(self.output, "/Pattern CS {} SCN", name)
+    }
+    /// Set a shading as color for non-stroking operations, e.g. to fill the
+    /// current path with a gradient.
+    ///
+    /// The coordinates of the shading refer to the default coordinate space
+    /// of the page and are not affected by [concat](#method.concat).
+    pub fn set_fill_shading(&mut self, shading: &Shading) -> Result<()> {
+        let name = self.shading_pattern_name(shading)?;
+        writeln!(self.output, "/Pattern cs {} scn", name)
+    }
+    fn shading_pattern_name(&mut self, shading: &Shading) -> Result<String> {
+        let shading_id = shading.write_object(self.pdf)?;
+        let pattern_id = self.pdf.dictionary_object_id(format!(
+            "<< /Type /Pattern /PatternType 2 /Shading {} 0 R >>",
+            shading_id
+        ))?;
+        Ok(self.resources.pattern_name(pattern_id))
+    }
+    /// Paint a shading in the current coordinate space over the whole
+    /// clipping region. Use [clip](#method.clip) to restrict the painted
+    /// area.
+    pub fn shade(&mut self, shading: &Shading) -> Result<()> {
+        let shading_id = shading.write_object(self.pdf)?;
+        let name = self.resources.shading_name(shading_id);
+        writeln!(self.output, "{} sh", name)
     }
 
     /// Modify the current transformation matrix for coordinates by
@@ -134,6 +424,46 @@ impl<'a> Canvas<'a> {
     ) -> Result<()> {
         write!(self.output, "{} {} m ", x, y)
     }
+    /// Add a straight line from the current point to `point` to the
+    /// current path.
+    pub fn line_to_point<T: LengthUnit>(
+        &mut self,
+        point: Point<T>,
+    ) -> Result<()> {
+        self.line_to(point.x, point.y)
+    }
+    /// Begin a new subpath at `point`.
+    pub fn move_to_point<T: LengthUnit>(
+        &mut self,
+        point: Point<T>,
+    ) -> Result<()> {
+        self.move_to(point.x, point.y)
+    }
+    /// Add a Bézier curve from the current point to `end` with `control1`
+    /// and `control2` as Bézier control points.
+    pub fn curve_to_points<T: LengthUnit>(
+        &mut self,
+        control1: Point<T>,
+        control2: Point<T>,
+        end: Point<T>,
+    ) -> Result<()> {
+        let (c1, c2) = (control1, control2);
+        self.curve_to(c1.x, c1.y, c2.x, c2.y, end.x, end.y)
+    }
+    /// Append all segments of `path` to the current path.
+    pub fn path<T: LengthUnit>(&mut self, path: &Path<T>) -> Result<()> {
+        for segment in path.segments() {
+            match *segment {
+                Segment::MoveTo(p) => self.move_to_point(p)?,
+                Segment::LineTo(p) => self.line_to_point(p)?,
+                Segment::CurveTo(c1, c2, p) => {
+                    self.curve_to_points(c1, c2, p)?
+                }
+                Segment::Close => writeln!(self.output, "h")?,
+            }
+        }
+        Ok(())
+    }
     /// Add an Bézier curve from the current point to (x3, y3) with (x1, y1)
     /// and (x2, y2) as Bézier control points.
     pub fn curve_to<T: LengthUnit>(
@@ -186,10 +516,23 @@ impl<'a> Canvas<'a> {
     pub fn fill(&mut self) -> Result<()> {
         writeln!(self.output, "f")
     }
+    /// Intersect the clipping region with the current path, using the
+    /// nonzero winding number rule, and end the path without painting it.
+    /// The clipping region can only be enlarged again by restoring the
+    /// graphics state.
+    pub fn clip(&mut self) -> Result<()> {
+        writeln!(self.output, "W n")
+    }
+    /// Intersect the clipping region with the current path, using the
+    /// even-odd rule, and end the path without painting it.
+    pub fn clip_even_odd(&mut self) -> Result<()> {
+        writeln!(self.output, "W* n")
+    }
     /// Get a FontRef for a specific font.
     pub fn get_font<F: FontSource>(&mut self, font: &F) -> FontRef {
-        let next_n = self.fonts.len();
-        self.fonts
+        let next_n = self.resources.fonts.len();
+        self.resources
+            .fonts
             .entry(Font::from_src(font))
             .or_insert_with(|| {
                 FontRef::new(
@@ -211,7 +554,8 @@ impl<'a> Canvas<'a> {
         F: FnOnce(&mut TextObject) -> Result<T>,
     {
         writeln!(self.output, "BT")?;
-        let result = render_text(&mut TextObject::new(self.output))?;
+        let result =
+            render_text(&mut TextObject::new(self.output, self.resources))?;
         writeln!(self.output, "ET")?;
         Ok(result)
     }
@@ -281,12 +625,134 @@ impl<'a> Canvas<'a> {
     /// Add an item for this page in the document outline.
     ///
     /// An outline item associates a name (contained in an ordered tree) with a
-    /// location in the document. The PDF standard supports several ways to
-    /// specify an exact location on a page, but this implementation currently
-    /// only supports linking to a specific page (the page that this Canvas is
-    /// for).
-    pub fn add_outline(&mut self, title: &str) {
-        self.outline_items.push(OutlineItem::new(title));
+    /// location in the document. This item shows the top of this page,
+    /// use [add_outline_at](#method.add_outline_at) or
+    /// [add_outline_item](#method.add_outline_item) for other locations.
+    pub fn add_outline(&mut self, title: &str) -> OutlineId {
+        self.add_outline_item(OutlineItem::new(title))
+    }
+
+    /// Add an item to the document outline that shows this page scrolled to
+    /// vertical position `y`.
+    pub fn add_outline_at<T: LengthUnit>(
+        &mut self,
+        title: &str,
+        y: UserSpace<T>,
+    ) -> OutlineId {
+        let mut item = OutlineItem::new(title);
+        item.set_fit(Fit::top(y));
+        self.add_outline_item(item)
+    }
+
+    /// Add an item pointing to this page to the document outline.  The
+    /// returned id can be used as the parent of other items.
+    ///
+    /// Items added while rendering a form or a pattern are ignored, as
+    /// they are not on a page, and their children are put at the top level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate simple_pdf;
+    /// # use simple_pdf::units::{Points, UserSpace};
+    /// # use simple_pdf::{Fit, OutlineItem, Pdf};
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// # let mut document = Pdf::create("foo.pdf")?;
+    /// document.render_page(pt!(400), pt!(600), |canvas| {
+    ///     let chapter = canvas.add_outline("Chapter 1");
+    ///     let mut section = OutlineItem::new("Section 1.1");
+    ///     section.set_parent(chapter);
+    ///     section.set_fit(Fit::xyz(pt!(0), pt!(300), Some(2.0)));
+    ///     section.set_italic(true);
+    ///     canvas.add_outline_item(section);
+    ///     Ok(())
+    /// })?;
+    /// # document.finish()
+    /// # }
+    /// ```
+    pub fn add_outline_item(&mut self, item: OutlineItem) -> OutlineId {
+        let id = OutlineId(self.pdf.next_outline_id);
+        self.pdf.next_outline_id += 1;
+        self.outline_items.push((id, item));
+        id
+    }
+
+    /// Add a link to a URL, that is activated by clicking in the rectangle
+    /// with a corner at (x, y) and extending width × height.
+    ///
+    /// The rectangle refers to the default coordinate space of the page and
+    /// is not affected by [concat](#method.concat).  Links added while
+    /// rendering a form or a pattern are ignored, as they are not on a page.
+    pub fn link_uri<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        uri: &str,
+    ) {
+        let rect = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        self.links.push(Link::uri(rect, uri));
+    }
+
+    /// Add a link to the page with index `page_index`, counting from 0, that
+    /// is activated by clicking in the rectangle with a corner at (x, y) and
+    /// extending width × height. The linked page may be rendered later.
+    ///
+    /// The rectangle refers to the default coordinate space of the page and
+    /// is not affected by [concat](#method.concat).  Links added while
+    /// rendering a form or a pattern are ignored, as they are not on a page.
+    pub fn link_to_page<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        page_index: usize,
+        fit: Fit,
+    ) {
+        let rect = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        self.links.push(Link::page(rect, page_index, fit));
+    }
+
+    /// Add a link to the destination added with
+    /// [add_named_destination](#method.add_named_destination) as `name`,
+    /// that is activated by clicking in the rectangle with a corner at
+    /// (x, y) and extending width × height.
+    ///
+    /// The rectangle refers to the default coordinate space of the page and
+    /// is not affected by [concat](#method.concat).  Links added while
+    /// rendering a form or a pattern are ignored, as they are not on a page.
+    pub fn link_to_destination<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        name: &str,
+    ) {
+        let rect = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        self.links.push(Link::named(rect, name));
+    }
+
+    /// Add a destination called `name` that shows this page with (x, y) at
+    /// the top left of the window.
+    ///
+    /// Named destinations can be the target of links in this document and
+    /// of links to it from elsewhere, e.g. `report.pdf#section-3`.  Adding
+    /// a destination with the same name again replaces it.  Destinations
+    /// added while rendering a form or a pattern are ignored, as they are
+    /// not on a page.
+    pub fn add_named_destination<T: LengthUnit>(
+        &mut self,
+        name: &str,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+    ) {
+        self.destinations
+            .push((name.to_string(), Fit::xyz(x, y, None)));
     }
 
     /// Save the current graphics state.
@@ -300,3 +766,12 @@ impl<'a> Canvas<'a> {
         writeln!(self.output, "Q")
     }
 }
+
+/// Check that an opacity is a number. Other values are clamped to the range
+/// from 0 to 1 when written.
+fn check_alpha(alpha: f32) -> Result<()> {
+    if alpha.is_nan() {
+        return Err(Error::new(ErrorKind::InvalidInput, "alpha can't be NaN"));
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+#[cfg(test)]
+use units::Points;
+use units::{LengthUnit, UserSpace};
+
+/// How a page is displayed when it is reached through a link, as described
+/// in section 12.3.2.2 of the PDF specification.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fit {
+    kind: FitKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FitKind {
+    Page,
+    Width(f32),
+    Height(f32),
+    Rect([f32; 4]),
+    Xyz(Option<f32>, Option<f32>, Option<f32>),
+}
+
+impl Fit {
+    /// Fit the whole page in the window.
+    pub fn page() -> Self {
+        Fit {
+            kind: FitKind::Page,
+        }
+    }
+
+    /// Fit the width of the page in the window, with the coordinate `top`
+    /// at the top edge of the window.
+    pub fn width<T: LengthUnit>(top: UserSpace<T>) -> Self {
+        Fit {
+            kind: FitKind::Width(top.pt),
+        }
+    }
+
+    /// Fit the height of the page in the window, with the coordinate `left`
+    /// at the left edge of the window.
+    pub fn height<T: LengthUnit>(left: UserSpace<T>) -> Self {
+        Fit {
+            kind: FitKind::Height(left.pt),
+        }
+    }
+
+    /// Fit the rectangle with a corner at (x, y) and extending width ×
+    /// height in the window.
+    pub fn rect<T: LengthUnit>(
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Self {
+        Fit {
+            kind: FitKind::Rect([x.pt, y.pt, (x + width).pt, (y + height).pt]),
+        }
+    }
+
+    /// Show the point (left, top) at the upper left corner of the window,
+    /// magnified by `zoom`. `None` keeps the current zoom of the viewer.
+    pub fn xyz<T: LengthUnit>(
+        left: UserSpace<T>,
+        top: UserSpace<T>,
+        zoom: Option<f32>,
+    ) -> Self {
+        Fit {
+            kind: FitKind::Xyz(Some(left.pt), Some(top.pt), zoom),
+        }
+    }
+
+    /// Scroll to the coordinate `top`, keeping the current horizontal
+    /// position and zoom of the viewer.
+    pub fn top<T: LengthUnit>(top: UserSpace<T>) -> Self {
+        Fit {
+            kind: FitKind::Xyz(None, Some(top.pt), None),
+        }
+    }
+}
+
+impl Default for Fit {
+    /// Keep the current position and zoom of the viewer.
+    fn default() -> Self {
+        Fit {
+            kind: FitKind::Xyz(None, None, None),
+        }
+    }
+}
+
+impl fmt::Display for Fit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let or_null = |value: Option<f32>| match value {
+            Some(value) => value.to_string(),
+            None => "null".to_string(),
+        };
+        match self.kind {
+            FitKind::Page => write!(f, "/Fit"),
+            FitKind::Width(top) => write!(f, "/FitH {}", top),
+            FitKind::Height(left) => write!(f, "/FitV {}", left),
+            FitKind::Rect(r) => {
+                write!(f, "/FitR {} {} {} {}", r[0], r[1], r[2], r[3])
+            }
+            FitKind::Xyz(left, top, zoom) => write!(
+                f,
+                "/XYZ {} {} {}",
+                or_null(left),
+                or_null(top),
+                or_null(zoom)
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_fit() {
+    assert_eq!("/Fit", Fit::page().to_string());
+    assert_eq!("/FitH 700", Fit::width(pt!(700)).to_string());
+    assert_eq!(
+        "/FitR 10 20 110 70",
+        Fit::rect(pt!(10), pt!(20), pt!(100), pt!(50)).to_string()
+    );
+    assert_eq!(
+        "/XYZ 0 500 1.5",
+        Fit::xyz(pt!(0), pt!(500), Some(1.5)).to_string()
+    );
+    assert_eq!("/XYZ null 500 null", Fit::top(pt!(500)).to_string());
+    assert_eq!("/XYZ null null null", Fit::default().to_string());
+}
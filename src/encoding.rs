@@ -68,6 +68,12 @@ impl FontEncoding {
     //     self.base = MacExpertEncoding;
     // }
 
+    /// Convert a String to the codes of its characters in the encoding. For
+    /// examples, see the Encoding documentation.
+    pub fn encode_bytes(&self, text: &str) -> Vec<u8> {
+        self.encoding.encode_bytes(text)
+    }
+
     /// Convert a String to a vector of bytes in the encoding. For examples,
     /// see the Encoding documentation.
     pub fn encode_string(&self, text: &str) -> Vec<u8> {
@@ -175,7 +181,10 @@ impl Encoding {
         self.unicode_to_code.get(&ch).cloned()
     }
 
-    /// Convert a rust string to a vector of bytes in the encoding.
+    /// Convert a rust string to a vector of bytes in the encoding, with
+    /// backslashes and parentheses escaped for a literal string.
+    ///
+    /// See [encode_bytes](#method.encode_bytes) for the unescaped codes.
     /// # Example
     /// ````
     /// use simple_pdf::{BuiltinFont, FontSource};
@@ -215,6 +224,24 @@ impl Encoding {
         result
     }
 
+    /// Convert a rust string to the codes of its characters in the
+    /// encoding.  Characters that are not in the encoding are replaced by
+    /// `?`.
+    /// # Example
+    /// ````
+    /// use simple_pdf::{BuiltinFont, FontSource};
+    /// let enc = BuiltinFont::Helvetica.encoding();
+    /// assert_eq!(
+    ///     vec![92, 40, 65, 66, 67, 41, 13, 128],
+    ///     enc.encode_bytes("\\(ABC)\r€")
+    /// );
+    /// ````
+    pub fn encode_bytes(&self, text: &str) -> Vec<u8> {
+        text.chars()
+            .map(|ch| self.encode_char(ch).unwrap_or(b'?'))
+            .collect()
+    }
+
     fn init_block(&mut self, start: u8, data: &[&'static str]) {
         for (i, name) in data.iter().enumerate() {
             self.name_to_code.insert(name, start + (i as u8));
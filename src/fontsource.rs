@@ -68,7 +68,7 @@ impl FontSource for BuiltinFont {
 
     fn raw_text_width(&self, text: &str) -> u32 {
         self.encoding()
-            .encode_string(text)
+            .encode_bytes(text)
             .iter()
             .fold(0, |result, &ch| {
                 result + u32::from(self.metrics().get_width(ch).unwrap_or(100))
@@ -0,0 +1,344 @@
+//! Types for points, vectors, sizes, rectangles and paths in user space.
+//!
+//! These can be transformed by a
+//! [Matrix](../graphicsstate/struct.Matrix.html) and passed to the methods
+//! of a [Canvas](../struct.Canvas.html) that take them.
+//!
+//! # Example
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate simple_pdf;
+//! # use simple_pdf::geometry::Rect;
+//! # use simple_pdf::units::{Points, UserSpace};
+//! # fn main() {
+//! let page = Rect::new(pt!(0), pt!(0), pt!(400), pt!(600));
+//! let content = page.inset(pt!(20), pt!(20));
+//! let (sidebar, main) = content.split_x(pt!(100));
+//! assert_eq!(pt!(120), main.min_x());
+//! assert_eq!(Some(sidebar), sidebar.intersection(&content));
+//! # }
+//! ```
+use std::ops::{Add, Mul, Neg, Sub};
+use units::{LengthUnit, UserSpace};
+
+mod path;
+mod stroke;
+pub use self::path::{FillRule, Path, Segment};
+pub use self::stroke::StrokeStyle;
+
+/// A position in user space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point<T: LengthUnit> {
+    /// The horizontal coordinate.
+    pub x: UserSpace<T>,
+    /// The vertical coordinate.
+    pub y: UserSpace<T>,
+}
+
+/// A displacement in user space, e.g. the difference of two points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vector<T: LengthUnit> {
+    /// The horizontal displacement.
+    pub dx: UserSpace<T>,
+    /// The vertical displacement.
+    pub dy: UserSpace<T>,
+}
+
+/// The extent of something in user space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Size<T: LengthUnit> {
+    /// The horizontal extent.
+    pub width: UserSpace<T>,
+    /// The vertical extent.
+    pub height: UserSpace<T>,
+}
+
+/// An axis-aligned rectangle, with a corner at `origin` and extending by
+/// `size`.
+///
+/// Rectangles created by the methods of this type have a non-negative
+/// size, so the origin is the lower left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect<T: LengthUnit> {
+    /// The lower left corner.
+    pub origin: Point<T>,
+    /// The width and height.
+    pub size: Size<T>,
+}
+
+impl<T: LengthUnit> Point<T> {
+    /// Create the point (x, y).
+    pub fn new(x: UserSpace<T>, y: UserSpace<T>) -> Self {
+        Point { x, y }
+    }
+}
+
+impl<T: LengthUnit> Vector<T> {
+    /// Create the vector (dx, dy).
+    pub fn new(dx: UserSpace<T>, dy: UserSpace<T>) -> Self {
+        Vector { dx, dy }
+    }
+
+    /// The length of the vector.
+    pub fn length(&self) -> UserSpace<T> {
+        UserSpace::from_pt(self.dx.pt.hypot(self.dy.pt))
+    }
+}
+
+impl<T: LengthUnit> Size<T> {
+    /// Create the size width × height.
+    pub fn new(width: UserSpace<T>, height: UserSpace<T>) -> Self {
+        Size { width, height }
+    }
+}
+
+impl<T: LengthUnit> Rect<T> {
+    /// Create the rectangle with a corner at (x, y) and extending width ×
+    /// height. Negative sizes extend to the left or down.
+    pub fn new(
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Self {
+        Rect::from_points(Point::new(x, y), Point::new(x + width, y + height))
+    }
+
+    /// Create the smallest rectangle that contains both points.
+    pub fn from_points(a: Point<T>, b: Point<T>) -> Self {
+        let min = |a: UserSpace<T>, b: UserSpace<T>| if a < b { a } else { b };
+        let origin = Point::new(min(a.x, b.x), min(a.y, b.y));
+        Rect {
+            origin,
+            size: Size::new(
+                UserSpace::from_pt((a.x - b.x).abs()),
+                UserSpace::from_pt((a.y - b.y).abs()),
+            ),
+        }
+    }
+
+    /// The left edge.
+    pub fn min_x(&self) -> UserSpace<T> {
+        self.origin.x
+    }
+    /// The bottom edge.
+    pub fn min_y(&self) -> UserSpace<T> {
+        self.origin.y
+    }
+    /// The right edge.
+    pub fn max_x(&self) -> UserSpace<T> {
+        self.origin.x + self.size.width
+    }
+    /// The top edge.
+    pub fn max_y(&self) -> UserSpace<T> {
+        self.origin.y + self.size.height
+    }
+    /// The width of the rectangle.
+    pub fn width(&self) -> UserSpace<T> {
+        self.size.width
+    }
+    /// The height of the rectangle.
+    pub fn height(&self) -> UserSpace<T> {
+        self.size.height
+    }
+    /// The center of the rectangle.
+    pub fn center(&self) -> Point<T> {
+        Point::new(
+            self.origin.x + self.size.width / 2.0,
+            self.origin.y + self.size.height / 2.0,
+        )
+    }
+    /// The corners of the rectangle, counterclockwise from the origin.
+    pub fn corners(&self) -> [Point<T>; 4] {
+        [
+            Point::new(self.min_x(), self.min_y()),
+            Point::new(self.max_x(), self.min_y()),
+            Point::new(self.max_x(), self.max_y()),
+            Point::new(self.min_x(), self.max_y()),
+        ]
+    }
+
+    /// Whether `point` is inside the rectangle or on its edge.
+    pub fn contains(&self, point: Point<T>) -> bool {
+        self.min_x() <= point.x
+            && point.x <= self.max_x()
+            && self.min_y() <= point.y
+            && point.y <= self.max_y()
+    }
+
+    /// The smallest rectangle that contains both rectangles.
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        let max = |a: UserSpace<T>, b: UserSpace<T>| if a > b { a } else { b };
+        let min = |a: UserSpace<T>, b: UserSpace<T>| if a < b { a } else { b };
+        Rect::from_points(
+            Point::new(
+                min(self.min_x(), other.min_x()),
+                min(self.min_y(), other.min_y()),
+            ),
+            Point::new(
+                max(self.max_x(), other.max_x()),
+                max(self.max_y(), other.max_y()),
+            ),
+        )
+    }
+
+    /// The area covered by both rectangles, or `None` if they don't
+    /// overlap. Rectangles that only touch intersect in an empty rectangle.
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let max = |a: UserSpace<T>, b: UserSpace<T>| if a > b { a } else { b };
+        let min = |a: UserSpace<T>, b: UserSpace<T>| if a < b { a } else { b };
+        let low = Point::new(
+            max(self.min_x(), other.min_x()),
+            max(self.min_y(), other.min_y()),
+        );
+        let high = Point::new(
+            min(self.max_x(), other.max_x()),
+            min(self.max_y(), other.max_y()),
+        );
+        if low.x <= high.x && low.y <= high.y {
+            Some(Rect::from_points(low, high))
+        } else {
+            None
+        }
+    }
+
+    /// Move the edges inwards by `dx` on the left and right and by `dy` at
+    /// the bottom and top. Negative values move them outwards. The result
+    /// is collapsed to its center if it would be negative.
+    pub fn inset(&self, dx: UserSpace<T>, dy: UserSpace<T>) -> Rect<T> {
+        let center = self.center();
+        let half_width: UserSpace<T> =
+            UserSpace::from_pt((self.width().pt / 2.0 - dx.pt).max(0.0));
+        let half_height: UserSpace<T> =
+            UserSpace::from_pt((self.height().pt / 2.0 - dy.pt).max(0.0));
+        Rect::from_points(
+            Point::new(center.x - half_width, center.y - half_height),
+            Point::new(center.x + half_width, center.y + half_height),
+        )
+    }
+
+    /// Split the rectangle into a left part `distance` wide, and a right
+    /// part with the rest. `distance` is clamped to the width.
+    pub fn split_x(&self, distance: UserSpace<T>) -> (Rect<T>, Rect<T>) {
+        let distance =
+            UserSpace::from_pt(distance.pt.clamp(0.0, self.width().pt));
+        (
+            Rect::new(self.min_x(), self.min_y(), distance, self.height()),
+            Rect::new(
+                self.min_x() + distance,
+                self.min_y(),
+                self.width() - distance,
+                self.height(),
+            ),
+        )
+    }
+
+    /// Split the rectangle into a bottom part `distance` high, and a top
+    /// part with the rest. `distance` is clamped to the height.
+    pub fn split_y(&self, distance: UserSpace<T>) -> (Rect<T>, Rect<T>) {
+        let distance =
+            UserSpace::from_pt(distance.pt.clamp(0.0, self.height().pt));
+        (
+            Rect::new(self.min_x(), self.min_y(), self.width(), distance),
+            Rect::new(
+                self.min_x(),
+                self.min_y() + distance,
+                self.width(),
+                self.height() - distance,
+            ),
+        )
+    }
+}
+
+impl<T: LengthUnit> Add<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+    fn add(self, v: Vector<T>) -> Point<T> {
+        Point::new(self.x + v.dx, self.y + v.dy)
+    }
+}
+
+impl<T: LengthUnit> Sub<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+    fn sub(self, v: Vector<T>) -> Point<T> {
+        Point::new(self.x - v.dx, self.y - v.dy)
+    }
+}
+
+impl<T: LengthUnit> Sub for Point<T> {
+    type Output = Vector<T>;
+    fn sub(self, other: Point<T>) -> Vector<T> {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: LengthUnit> Add for Vector<T> {
+    type Output = Vector<T>;
+    fn add(self, v: Vector<T>) -> Vector<T> {
+        Vector::new(self.dx + v.dx, self.dy + v.dy)
+    }
+}
+
+impl<T: LengthUnit> Sub for Vector<T> {
+    type Output = Vector<T>;
+    fn sub(self, v: Vector<T>) -> Vector<T> {
+        Vector::new(self.dx - v.dx, self.dy - v.dy)
+    }
+}
+
+impl<T: LengthUnit> Mul<f32> for Vector<T> {
+    type Output = Vector<T>;
+    fn mul(self, factor: f32) -> Vector<T> {
+        Vector::new(self.dx * factor, self.dy * factor)
+    }
+}
+
+impl<T: LengthUnit> Neg for Vector<T> {
+    type Output = Vector<T>;
+    fn neg(self) -> Vector<T> {
+        Vector::new(-self.dx, -self.dy)
+    }
+}
+
+#[cfg(test)]
+use units::Points;
+
+#[test]
+fn test_rect_operations() {
+    let a = Rect::new(pt!(10), pt!(10), pt!(-10), pt!(20));
+    assert_eq!(Rect::new(pt!(0), pt!(10), pt!(10), pt!(20)), a);
+    let b = Rect::new(pt!(5), pt!(0), pt!(10), pt!(15));
+    assert_eq!(Rect::new(pt!(0), pt!(0), pt!(15), pt!(30)), a.union(&b));
+    assert_eq!(
+        Some(Rect::new(pt!(5), pt!(10), pt!(5), pt!(5))),
+        a.intersection(&b)
+    );
+    let far = Rect::new(pt!(100), pt!(100), pt!(1), pt!(1));
+    assert_eq!(None, a.intersection(&far));
+    assert_eq!(
+        Rect::new(pt!(2), pt!(11), pt!(6), pt!(18)),
+        a.inset(pt!(2), pt!(1))
+    );
+    assert_eq!(
+        Rect::new(pt!(5), pt!(20), pt!(0), pt!(0)),
+        a.inset(pt!(6), pt!(11))
+    );
+    let (bottom, top) = a.split_y(pt!(5));
+    assert_eq!(Rect::new(pt!(0), pt!(10), pt!(10), pt!(5)), bottom);
+    assert_eq!(Rect::new(pt!(0), pt!(15), pt!(10), pt!(15)), top);
+    let (left, right) = a.split_x(pt!(50));
+    assert_eq!((a, pt!(10)), (left, right.min_x()));
+    assert!(a.contains(Point::new(pt!(10), pt!(30))));
+    assert!(!a.contains(Point::new(pt!(10.5), pt!(30))));
+}
+
+#[test]
+fn test_point_arithmetic() {
+    let p = Point::new(pt!(1), pt!(2));
+    let q = Point::new(pt!(4), pt!(6));
+    let v = q - p;
+    assert_eq!(pt!(5), v.length());
+    assert_eq!(q, p + v);
+    assert_eq!(p, q - v);
+    assert_eq!(Vector::new(pt!(-6), pt!(-8)), -(v * 2.0));
+}
@@ -0,0 +1,529 @@
+use super::{Point, Rect};
+use graphicsstate::Matrix;
+use units::{LengthUnit, UserSpace};
+
+/// A part of a [Path](struct.Path.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment<T: LengthUnit> {
+    /// Begin a new subpath at the point.
+    MoveTo(Point<T>),
+    /// A straight line from the current point.
+    LineTo(Point<T>),
+    /// A cubic Bézier curve from the current point, with two control points
+    /// and the end point.
+    CurveTo(Point<T>, Point<T>, Point<T>),
+    /// A straight line back to the start of the subpath, which ends it.
+    Close,
+}
+
+/// The rule that decides which points are inside a path that crosses
+/// itself or has holes, as described in section 8.5.3.3 of the PDF
+/// specification.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum FillRule {
+    /// A point is inside if the path winds around it a nonzero number of
+    /// times, counting clockwise and counterclockwise turns against each
+    /// other.  This is the rule of `Canvas::fill` and `Canvas::clip`.
+    NonZero,
+    /// A point is inside if a ray from it crosses the path an odd number of
+    /// times.  This is the rule of `Canvas::clip_even_odd`.
+    EvenOdd,
+}
+
+/// A path made of straight lines and Bézier curves, that can be measured
+/// before it is drawn with `Canvas::path`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate simple_pdf;
+/// # use simple_pdf::geometry::{FillRule, Path, Point, Rect};
+/// # use simple_pdf::units::{Points, UserSpace};
+/// # fn main() {
+/// let mut logo = Path::new();
+/// logo.circle(Point::new(pt!(50), pt!(50)), pt!(20));
+/// let bbox = logo.bounding_box().unwrap();
+/// assert_eq!(pt!(30), bbox.min_x());
+/// assert!(logo.contains(Point::new(pt!(50), pt!(60)), FillRule::NonZero));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<T: LengthUnit> {
+    segments: Vec<Segment<T>>,
+}
+
+impl<T: LengthUnit> Default for Path<T> {
+    fn default() -> Self {
+        Path::new()
+    }
+}
+
+impl<T: LengthUnit> Path<T> {
+    /// Create an empty path.
+    pub fn new() -> Self {
+        Path {
+            segments: Vec::new(),
+        }
+    }
+
+    /// The segments of the path.
+    pub fn segments(&self) -> &[Segment<T>] {
+        &self.segments
+    }
+
+    /// Whether the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Begin a new subpath at `point`.
+    pub fn move_to(&mut self, point: Point<T>) {
+        self.segments.push(Segment::MoveTo(point));
+    }
+
+    /// Add a straight line from the current point to `point`.
+    pub fn line_to(&mut self, point: Point<T>) {
+        self.segments.push(Segment::LineTo(point));
+    }
+
+    /// Add a Bézier curve from the current point to `end` with `control1`
+    /// and `control2` as control points.
+    pub fn curve_to(
+        &mut self,
+        control1: Point<T>,
+        control2: Point<T>,
+        end: Point<T>,
+    ) {
+        self.segments
+            .push(Segment::CurveTo(control1, control2, end));
+    }
+
+    /// Close the current subpath with a straight line to its start.
+    pub fn close(&mut self) {
+        self.segments.push(Segment::Close);
+    }
+
+    /// Add `rect` as a closed subpath, counterclockwise from its origin.
+    pub fn rect(&mut self, rect: &Rect<T>) {
+        let corners = rect.corners();
+        self.move_to(corners[0]);
+        for &corner in &corners[1..] {
+            self.line_to(corner);
+        }
+        self.close();
+    }
+
+    /// Add a circle approximated by four Bézier curves as a closed
+    /// subpath, like `Canvas::circle`.
+    pub fn circle(&mut self, center: Point<T>, radius: UserSpace<T>) {
+        // See Canvas::circle.
+        let c = 0.551_915_05;
+        let (x, y, r) = (center.x.pt, center.y.pt, radius.pt);
+        let p = |x: f32, y: f32| Point::new(pt(x), pt(y));
+        self.move_to(p(x, y - r));
+        self.curve_to(p(x - r * c, y - r), p(x - r, y - r * c), p(x - r, y));
+        self.curve_to(p(x - r, y + r * c), p(x - r * c, y + r), p(x, y + r));
+        self.curve_to(p(x + r * c, y + r), p(x + r, y + r * c), p(x + r, y));
+        self.curve_to(p(x + r, y - r * c), p(x + r * c, y - r), p(x, y - r));
+        self.close();
+    }
+
+    /// Append all segments of `other`.
+    pub fn extend(&mut self, other: &Path<T>) {
+        self.segments.extend_from_slice(&other.segments);
+    }
+
+    /// Return the path with all points transformed by `matrix`.
+    pub fn transform(&self, matrix: &Matrix) -> Path<T> {
+        let p = |point: Point<T>| matrix.transform_point(point);
+        Path {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| match *segment {
+                    Segment::MoveTo(a) => Segment::MoveTo(p(a)),
+                    Segment::LineTo(a) => Segment::LineTo(p(a)),
+                    Segment::CurveTo(a, b, c) => {
+                        Segment::CurveTo(p(a), p(b), p(c))
+                    }
+                    Segment::Close => Segment::Close,
+                })
+                .collect(),
+        }
+    }
+
+    /// The smallest rectangle that contains every line and curve of the
+    /// path, or `None` if it draws nothing.  Curves are measured exactly,
+    /// not by their control points.
+    pub fn bounding_box(&self) -> Option<Rect<T>> {
+        let mut bounds: Option<[f32; 4]> = None;
+        let mut include = |p: [f32; 2]| {
+            let b = bounds.get_or_insert([p[0], p[1], p[0], p[1]]);
+            *b = [
+                b[0].min(p[0]),
+                b[1].min(p[1]),
+                b[2].max(p[0]),
+                b[3].max(p[1]),
+            ];
+        };
+        for element in self.elements() {
+            match element {
+                Element::Line(a, b) => {
+                    include(a);
+                    include(b);
+                }
+                Element::Curve(curve) => {
+                    include(curve[0]);
+                    include(curve[3]);
+                    for axis in 0..2 {
+                        let c = [
+                            curve[0][axis],
+                            curve[1][axis],
+                            curve[2][axis],
+                            curve[3][axis],
+                        ];
+                        for t in cubic_extrema(c) {
+                            include(point_at(&curve, t));
+                        }
+                    }
+                }
+            }
+        }
+        bounds.map(|b| {
+            Rect::from_points(
+                Point::new(pt(b[0]), pt(b[1])),
+                Point::new(pt(b[2]), pt(b[3])),
+            )
+        })
+    }
+
+    /// Approximate the path by polylines, one for each subpath, that are no
+    /// further than `tolerance` from the curves.  Closed subpaths end with
+    /// their first point.
+    pub fn flatten(&self, tolerance: UserSpace<T>) -> Vec<Vec<Point<T>>> {
+        self.flatten_pt(tolerance.pt)
+            .into_iter()
+            .map(|polyline| {
+                polyline
+                    .into_iter()
+                    .map(|p| Point::new(pt(p[0]), pt(p[1])))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The total length of all lines and curves of the path.
+    pub fn length(&self) -> UserSpace<T> {
+        let length = self
+            .flatten_pt(LENGTH_TOLERANCE)
+            .iter()
+            .flat_map(|polyline| polyline.windows(2))
+            .map(|w| distance(w[0], w[1]))
+            .sum();
+        pt(length)
+    }
+
+    /// Whether `point` is inside the area that filling the path with
+    /// `rule` paints.  Open subpaths are implicitly closed, as when they
+    /// are filled.
+    pub fn contains(&self, point: Point<T>, rule: FillRule) -> bool {
+        let (x, y) = (point.x.pt, point.y.pt);
+        let mut winding = 0;
+        for polyline in self.flatten_pt(CONTAINS_TOLERANCE) {
+            let closing = [polyline[polyline.len() - 1], polyline[0]];
+            for edge in polyline.windows(2).chain(Some(&closing[..])) {
+                let (a, b) = (edge[0], edge[1]);
+                // Count edges crossing the horizontal ray to the right of
+                // the point, upward ones positive and downward negative.
+                if (a[1] <= y) != (b[1] <= y) {
+                    let t = (y - a[1]) / (b[1] - a[1]);
+                    if a[0] + t * (b[0] - a[0]) > x {
+                        winding += if b[1] > a[1] { 1 } else { -1 };
+                    }
+                }
+            }
+        }
+        match rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// The lines and curves of the path in points, with the lines that
+    /// close subpaths made explicit.
+    pub(crate) fn elements(&self) -> Vec<Element> {
+        self.subpaths()
+            .into_iter()
+            .flat_map(|subpath| subpath.elements)
+            .collect()
+    }
+
+    /// The subpaths of the path in points, with the lines that close them
+    /// made explicit.  A subpath that is only a move is dropped.
+    pub(crate) fn subpaths(&self) -> Vec<Subpath> {
+        let mut subpaths = Vec::new();
+        let mut current: Option<Subpath> = None;
+        // The point where a line or curve without a preceding move starts.
+        let mut last = None;
+        for segment in &self.segments {
+            let end = match *segment {
+                Segment::MoveTo(p) => {
+                    subpaths.extend(current.take().filter(Subpath::draws));
+                    current = Some(Subpath::new(raw(p)));
+                    last = Some(raw(p));
+                    continue;
+                }
+                Segment::LineTo(p) | Segment::CurveTo(_, _, p) => raw(p),
+                Segment::Close => {
+                    if let Some(mut subpath) = current.take() {
+                        let (end, start) = (subpath.end(), subpath.start);
+                        if end != start {
+                            subpath.elements.push(Element::Line(end, start));
+                        }
+                        subpath.closed = true;
+                        last = Some(start);
+                        subpaths.push(subpath);
+                    }
+                    continue;
+                }
+            };
+            let subpath = current
+                .get_or_insert_with(|| Subpath::new(last.unwrap_or(end)));
+            let start = subpath.end();
+            subpath.elements.push(match *segment {
+                Segment::CurveTo(c1, c2, _) => {
+                    Element::Curve([start, raw(c1), raw(c2), end])
+                }
+                _ => Element::Line(start, end),
+            });
+            last = Some(end);
+        }
+        subpaths.extend(current.filter(Subpath::draws));
+        subpaths
+    }
+
+    /// Flatten the path to polylines in points.  Subpaths without lines
+    /// or curves are dropped.
+    pub(crate) fn flatten_pt(&self, tolerance: f32) -> Vec<Vec<[f32; 2]>> {
+        self.subpaths()
+            .iter()
+            .filter(|subpath| !subpath.elements.is_empty())
+            .map(|subpath| subpath.flatten(tolerance))
+            .collect()
+    }
+}
+
+/// A subpath in points, as a start point and the lines and curves from
+/// there.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Subpath {
+    pub start: [f32; 2],
+    pub elements: Vec<Element>,
+    pub closed: bool,
+}
+
+impl Subpath {
+    fn new(start: [f32; 2]) -> Self {
+        Subpath {
+            start,
+            elements: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Whether stroking the subpath can paint anything, which a single
+    /// move cannot.
+    fn draws(&self) -> bool {
+        self.closed || !self.elements.is_empty()
+    }
+
+    /// The current point at the end of the subpath.
+    pub fn end(&self) -> [f32; 2] {
+        match self.elements.last() {
+            Some(&Element::Line(_, end)) => end,
+            Some(&Element::Curve(curve)) => curve[3],
+            None => self.start,
+        }
+    }
+
+    /// Approximate the subpath by a polyline no further than `tolerance`
+    /// from its curves.
+    pub fn flatten(&self, tolerance: f32) -> Vec<[f32; 2]> {
+        let mut polyline = vec![self.start];
+        for element in &self.elements {
+            match *element {
+                Element::Line(_, end) => polyline.push(end),
+                Element::Curve(ref curve) => {
+                    flatten_curve(curve, tolerance, &mut polyline)
+                }
+            }
+        }
+        polyline
+    }
+}
+
+/// A line or cubic Bézier curve in points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Element {
+    Line([f32; 2], [f32; 2]),
+    Curve([[f32; 2]; 4]),
+}
+
+/// The tolerance in points for measuring the length of paths.
+const LENGTH_TOLERANCE: f32 = 0.001;
+/// The tolerance in points for testing whether paths contain a point.
+const CONTAINS_TOLERANCE: f32 = 0.01;
+
+fn pt<T: LengthUnit>(pt: f32) -> UserSpace<T> {
+    UserSpace::from_pt(pt)
+}
+
+fn raw<T: LengthUnit>(p: Point<T>) -> [f32; 2] {
+    [p.x.pt, p.y.pt]
+}
+
+pub(crate) fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// The point of the cubic Bézier curve at `t`.
+pub(crate) fn point_at(curve: &[[f32; 2]; 4], t: f32) -> [f32; 2] {
+    let s = 1.0 - t;
+    let (b0, b1, b2, b3) =
+        (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+    let c = curve;
+    [
+        b0 * c[0][0] + b1 * c[1][0] + b2 * c[2][0] + b3 * c[3][0],
+        b0 * c[0][1] + b1 * c[1][1] + b2 * c[2][1] + b3 * c[3][1],
+    ]
+}
+
+/// The parameters strictly between 0 and 1 where the cubic Bézier function
+/// with coefficients `c` has a local minimum or maximum.
+fn cubic_extrema(c: [f32; 4]) -> Vec<f32> {
+    // The derivative divided by 3 is a t² + b t + k.
+    let a = -c[0] + 3.0 * c[1] - 3.0 * c[2] + c[3];
+    let b = 2.0 * (c[0] - 2.0 * c[1] + c[2]);
+    let k = c[1] - c[0];
+    let mut roots = Vec::with_capacity(2);
+    if a.abs() < 1e-6 * (b.abs() + k.abs()).max(1e-6) {
+        if b != 0.0 {
+            roots.push(-k / b);
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * k;
+        if discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            roots.push((-b + root) / (2.0 * a));
+            roots.push((-b - root) / (2.0 * a));
+        }
+    }
+    roots.retain(|&t| t > 0.0 && t < 1.0);
+    roots
+}
+
+/// Append points approximating the curve within `tolerance` to `output`,
+/// which must end with the start of the curve.
+pub(crate) fn flatten_curve(
+    curve: &[[f32; 2]; 4],
+    tolerance: f32,
+    output: &mut Vec<[f32; 2]>,
+) {
+    // Wang's formula gives the number of line segments needed.
+    let second_difference = |i: usize| {
+        let d = |axis: usize| {
+            curve[i][axis] - 2.0 * curve[i + 1][axis] + curve[i + 2][axis]
+        };
+        d(0).hypot(d(1))
+    };
+    let m = second_difference(0).max(second_difference(1));
+    let n = ((0.75 * m / tolerance.max(1e-6)).sqrt().ceil() as usize).max(1);
+    for i in 1..n {
+        output.push(point_at(curve, i as f32 / n as f32));
+    }
+    output.push(curve[3]);
+}
+
+#[cfg(test)]
+use units::Points;
+
+#[test]
+fn test_bounding_box_of_curve_extrema() {
+    let mut path = Path::new();
+    path.move_to(Point::new(pt!(0), pt!(0)));
+    // The control points reach y = 100, but the curve only reaches 75.
+    path.curve_to(
+        Point::new(pt!(0), pt!(100)),
+        Point::new(pt!(100), pt!(100)),
+        Point::new(pt!(100), pt!(0)),
+    );
+    let bbox = path.bounding_box().unwrap();
+    assert_eq!(Rect::new(pt!(0), pt!(0), pt!(100), pt!(75)), bbox);
+    assert_eq!(None, Path::<Points>::new().bounding_box());
+
+    let mut circle = Path::new();
+    circle.circle(Point::new(pt!(10), pt!(10)), pt!(10));
+    let bbox = circle.bounding_box().unwrap();
+    assert!((bbox.max_x().pt - 20.0).abs() < 1e-4);
+    assert!((bbox.min_y().pt).abs() < 1e-4);
+}
+
+#[test]
+fn test_length_and_flatten() {
+    let mut path = Path::new();
+    path.rect(&Rect::new(pt!(0), pt!(0), pt!(30), pt!(40)));
+    path.move_to(Point::new(pt!(0), pt!(0)));
+    path.line_to(Point::new(pt!(30), pt!(40)));
+    assert_eq!(pt!(190), path.length());
+    let polylines = path.flatten(pt!(0.1));
+    assert_eq!(2, polylines.len());
+    assert_eq!(5, polylines[0].len());
+    assert_eq!(polylines[0][0], polylines[0][4]);
+
+    let mut circle = Path::new();
+    circle.circle(Point::new(pt!(0), pt!(0)), pt!(10));
+    let length = circle.length().pt;
+    assert!((length - 20.0 * std::f32::consts::PI).abs() < 0.01);
+    for p in &circle.flatten(pt!(0.5))[0] {
+        let r = p.x.pt.hypot(p.y.pt);
+        assert!((r - 10.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn test_contains_fill_rules() {
+    // Two nested squares in the same direction, and a square to the left
+    // with a hole in the opposite direction.
+    let mut path = Path::new();
+    path.rect(&Rect::new(pt!(0), pt!(0), pt!(100), pt!(100)));
+    path.rect(&Rect::new(pt!(25), pt!(25), pt!(50), pt!(50)));
+    let mut hole = Path::new();
+    hole.rect(&Rect::new(pt!(25), pt!(25), pt!(50), pt!(50)));
+    path.extend(&hole.transform(&Matrix::scale(-1.0, 1.0)));
+    path.rect(&Rect::new(pt!(-100), pt!(0), pt!(100), pt!(100)));
+
+    let inner = Point::new(pt!(50), pt!(50));
+    assert!(path.contains(inner, FillRule::NonZero));
+    assert!(!path.contains(inner, FillRule::EvenOdd));
+    assert!(path.contains(Point::new(pt!(10), pt!(50)), FillRule::EvenOdd));
+    assert!(!path.contains(Point::new(pt!(150), pt!(50)), FillRule::NonZero));
+    let hole_point = Point::new(pt!(-50), pt!(50));
+    assert!(!path.contains(hole_point, FillRule::NonZero));
+    assert!(!path.contains(hole_point, FillRule::EvenOdd));
+}
+
+#[test]
+fn test_transform() {
+    let mut path = Path::new();
+    path.move_to(Point::new(pt!(1), pt!(2)));
+    path.line_to(Point::new(pt!(3), pt!(4)));
+    let moved = path.transform(&Matrix::translate(pt!(10), pt!(20)));
+    assert_eq!(
+        &[
+            Segment::MoveTo(Point::new(pt!(11), pt!(22))),
+            Segment::LineTo(Point::new(pt!(13), pt!(24))),
+        ],
+        moved.segments()
+    );
+}
@@ -0,0 +1,783 @@
+use super::path::{distance, flatten_curve, point_at, Element, Subpath};
+use super::{Path, Point};
+use graphicsstate::{CapStyle, JoinStyle};
+use std::f32::consts::PI;
+use units::{LengthUnit, UserSpace};
+
+/// The line width, join and cap styles, miter limit and dash pattern used
+/// to stroke a path, as set in the graphics state by
+/// `Canvas::set_stroke_style`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate simple_pdf;
+/// # use simple_pdf::geometry::{Path, Point, Segment, StrokeStyle};
+/// # use simple_pdf::graphicsstate::CapStyle;
+/// # use simple_pdf::units::{Points, UserSpace};
+/// # fn main() {
+/// let mut style = StrokeStyle::new(pt!(2));
+/// style.set_line_cap_style(CapStyle::Round);
+/// style.set_dash(&[pt!(6), pt!(4)], pt!(0));
+/// let mut line = Path::new();
+/// line.move_to(Point::new(pt!(0), pt!(0)));
+/// line.line_to(Point::new(pt!(100), pt!(0)));
+/// // Ten dashes, each a closed outline to fill or cut.
+/// let outline = line.stroke_outline(&style, pt!(0.1));
+/// let closed = outline.segments().iter().filter(|s| **s == Segment::Close);
+/// assert_eq!(10, closed.count());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle<T: LengthUnit> {
+    line_width: UserSpace<T>,
+    join_style: JoinStyle,
+    cap_style: CapStyle,
+    miter_limit: f32,
+    dash_pattern: Vec<UserSpace<T>>,
+    dash_phase: UserSpace<T>,
+}
+
+impl<T: LengthUnit> StrokeStyle<T> {
+    /// Create a style for lines of `line_width`, with the defaults of the
+    /// graphics state otherwise: mitered joins, butt caps, a miter limit of
+    /// 10 and no dashes.
+    pub fn new(line_width: UserSpace<T>) -> Self {
+        StrokeStyle {
+            line_width,
+            join_style: JoinStyle::Miter,
+            cap_style: CapStyle::Butt,
+            miter_limit: 10.0,
+            dash_pattern: Vec::new(),
+            dash_phase: UserSpace::from_pt(0.0),
+        }
+    }
+
+    /// Set the line join style.
+    pub fn set_line_join_style(&mut self, style: JoinStyle) {
+        self.join_style = style;
+    }
+
+    /// Set the line cap style.
+    pub fn set_line_cap_style(&mut self, style: CapStyle) {
+        self.cap_style = style;
+    }
+
+    /// Set the miter limit, the ratio of the length of a miter to the line
+    /// width above which mitered joins are beveled instead.  As with
+    /// `Canvas::set_miter_limit`, a limit less than 1 is ignored.
+    pub fn set_miter_limit(&mut self, limit: f32) {
+        if limit >= 1.0 {
+            self.miter_limit = limit;
+        }
+    }
+
+    /// Set the dash pattern of alternating dash and gap lengths, starting
+    /// `phase` into the pattern.  An empty pattern gives solid lines.  As
+    /// with `Canvas::set_dash`, a pattern with negative values or only
+    /// zeros is ignored.
+    pub fn set_dash(&mut self, pattern: &[UserSpace<T>], phase: UserSpace<T>) {
+        if pattern.is_empty() || valid_dash(pattern) {
+            self.dash_pattern = pattern.to_vec();
+            self.dash_phase = phase;
+        }
+    }
+
+    /// The line width.
+    pub fn line_width(&self) -> UserSpace<T> {
+        self.line_width
+    }
+
+    /// The line join style.
+    pub fn line_join_style(&self) -> JoinStyle {
+        self.join_style
+    }
+
+    /// The line cap style.
+    pub fn line_cap_style(&self) -> CapStyle {
+        self.cap_style
+    }
+
+    /// The miter limit.
+    pub fn miter_limit(&self) -> f32 {
+        self.miter_limit
+    }
+
+    /// The dash pattern and phase, with an empty pattern for solid lines.
+    pub fn dash(&self) -> (&[UserSpace<T>], UserSpace<T>) {
+        (&self.dash_pattern, self.dash_phase)
+    }
+}
+
+impl<T: LengthUnit> Path<T> {
+    /// Split the path into the dashes that stroking it with the dash
+    /// pattern `pattern` and `phase` draws, each an open subpath.  The
+    /// pattern restarts at every subpath, and a pattern with an odd number
+    /// of lengths repeats with dashes and gaps swapped, as in section
+    /// 8.4.3.6 of the PDF specification.
+    ///
+    /// A closed subpath that is a single dash stays closed, and a dash of
+    /// zero length becomes a line to its own start.  A subpath that the
+    /// pattern would split into more than a million dashes and gaps is left
+    /// solid.  The path is returned unchanged if the pattern is not valid
+    /// for `Canvas::set_dash`.
+    pub fn dashed(
+        &self,
+        pattern: &[UserSpace<T>],
+        phase: UserSpace<T>,
+    ) -> Path<T> {
+        if !valid_dash(pattern) {
+            return self.clone();
+        }
+        let pattern: Vec<f32> =
+            pattern.iter().map(|length| length.pt).collect();
+        let mut dasher = Dasher {
+            output: Path::new(),
+            start: None,
+            drawn: false,
+        };
+        for subpath in self.subpaths() {
+            dasher.dash(&subpath, &pattern, phase.pt);
+        }
+        dasher.output
+    }
+
+    /// The outline of the area that stroking the path with `style` paints,
+    /// as closed subpaths to fill with the nonzero winding rule.  Curves
+    /// are flattened to lines no further than `tolerance` from them, while
+    /// round joins and caps become arcs of Bézier curves.
+    ///
+    /// Like stroking, a subpath of a single point is only painted with
+    /// round caps, as a circle.
+    pub fn stroke_outline(
+        &self,
+        style: &StrokeStyle<T>,
+        tolerance: UserSpace<T>,
+    ) -> Path<T> {
+        let (pattern, phase) = style.dash();
+        let dashed;
+        let path = if pattern.is_empty() {
+            self
+        } else {
+            dashed = self.dashed(pattern, phase);
+            &dashed
+        };
+        let mut outline = Outline {
+            output: Path::new(),
+            style,
+            half_width: style.line_width.pt / 2.0,
+            current: None,
+        };
+        if outline.half_width <= 0.0 {
+            return outline.output;
+        }
+        for subpath in path.subpaths() {
+            let mut points = subpath.flatten(tolerance.pt);
+            points.dedup_by(|a, b| distance(*a, *b) < DEGENERATE_LENGTH);
+            if subpath.closed
+                && points.len() > 1
+                && distance(points[0], points[points.len() - 1])
+                    < DEGENERATE_LENGTH
+            {
+                points.pop();
+            }
+            if points.len() == 1 {
+                if style.cap_style == CapStyle::Round {
+                    let radius = UserSpace::from_pt(outline.half_width);
+                    outline.output.circle(point(points[0]), radius);
+                }
+            } else if subpath.closed {
+                outline.closed(&points);
+            } else {
+                outline.open(&points);
+            }
+        }
+        outline.output
+    }
+}
+
+/// Lines shorter than this many points are dropped before stroking, as
+/// they have no direction.
+const DEGENERATE_LENGTH: f32 = 1e-4;
+/// The most intervals of a dash pattern a subpath is split into.  Finer
+/// patterns leave the subpath solid, as renderers do when the dashes are
+/// too small to show.
+const MAX_DASHES: f32 = 1e6;
+/// The tolerance in points for finding dashes along curves.
+const DASH_TOLERANCE: f32 = 0.01;
+
+/// Whether `pattern` is valid for `Canvas::set_dash`, with no negative
+/// lengths and at least one that is not zero.
+fn valid_dash<T: LengthUnit>(pattern: &[UserSpace<T>]) -> bool {
+    pattern.iter().all(|length| length.pt >= 0.0)
+        && pattern.iter().any(|length| length.pt > 0.0)
+}
+
+fn point<T: LengthUnit>(p: [f32; 2]) -> Point<T> {
+    Point::new(UserSpace::from_pt(p[0]), UserSpace::from_pt(p[1]))
+}
+
+/// Splits subpaths into dashes.
+struct Dasher<T: LengthUnit> {
+    output: Path<T>,
+    /// The start of the current dash, if there is one.
+    start: Option<[f32; 2]>,
+    /// Whether the current dash has any lines or curves.
+    drawn: bool,
+}
+
+impl<T: LengthUnit> Dasher<T> {
+    fn dash(&mut self, subpath: &Subpath, pattern: &[f32], phase: f32) {
+        // An odd number of lengths repeats to an even one.
+        let count = pattern.len() * (1 + pattern.len() % 2);
+        let length = |index: usize| pattern[index % pattern.len()];
+        let total: f32 = (0..count).map(length).sum();
+        let samples: Vec<_> = subpath.elements.iter().map(measure).collect();
+        let subpath_length: f32 =
+            samples.iter().map(|s| s[s.len() - 1].1).sum();
+        if subpath_length / total * count as f32 > MAX_DASHES {
+            self.copy(subpath);
+            return;
+        }
+
+        // The offsets along the subpath where the intervals of the pattern
+        // end are computed from the start of their period rather than
+        // summed up, so that rounding errors do not accumulate.
+        let phase = phase.rem_euclid(total);
+        let ends_in_period: Vec<f32> = (0..count)
+            .scan(0.0, |sum, index| {
+                *sum += length(index);
+                Some(*sum)
+            })
+            .collect();
+        let end_of = |index: usize| {
+            (index / count) as f32 * total + ends_in_period[index % count]
+                - phase
+        };
+        let mut index = 0;
+        while phase > 0.0 && end_of(index) <= 0.0 {
+            index += 1;
+        }
+        let mut position = 0.0;
+        let mut element_start = 0.0;
+        let mut interrupted = false;
+
+        'elements: for (element, samples) in
+            subpath.elements.iter().zip(&samples)
+        {
+            let element_end = element_start + samples[samples.len() - 1].1;
+            loop {
+                let end = end_of(index);
+                let ends = end <= element_end;
+                let s0 = position - element_start;
+                let s1 = end.min(element_end) - element_start;
+                // A dash that starts at the end of the element is begun
+                // with the next one, unless it has zero length.
+                if index % 2 == 0 && (s1 > s0 || ends) {
+                    let t0 = parameter(samples, s0);
+                    self.begin(element_point(element, t0));
+                    if s1 > s0 {
+                        let t1 = parameter(samples, s1);
+                        self.draw(part(element, t0, t1));
+                    }
+                }
+                if !ends {
+                    break;
+                }
+                if index % 2 == 0 {
+                    self.end();
+                }
+                interrupted = true;
+                position = end.max(position);
+                index += 1;
+                // Give up on the subpath if the next interval is too short
+                // to advance at this precision.
+                if end_of(index) <= position && length(index) > 0.0 {
+                    break 'elements;
+                }
+            }
+            position = element_end;
+            element_start = element_end;
+        }
+
+        if subpath.elements.is_empty() && index % 2 == 0 {
+            self.begin(subpath.start);
+        }
+        if subpath.closed && !interrupted && self.start.is_some() {
+            self.output.close();
+            self.start = None;
+        } else {
+            self.end();
+        }
+    }
+
+    /// Add the subpath undashed.
+    fn copy(&mut self, subpath: &Subpath) {
+        self.begin(subpath.start);
+        for &element in &subpath.elements {
+            self.draw(element);
+        }
+        if subpath.closed {
+            self.output.close();
+            self.start = None;
+        } else {
+            self.end();
+        }
+    }
+
+    fn begin(&mut self, p: [f32; 2]) {
+        if self.start.is_none() {
+            self.output.move_to(point(p));
+            self.start = Some(p);
+            self.drawn = false;
+        }
+    }
+
+    fn draw(&mut self, element: Element) {
+        match element {
+            Element::Line(_, end) => self.output.line_to(point(end)),
+            Element::Curve(c) => {
+                self.output.curve_to(point(c[1]), point(c[2]), point(c[3]))
+            }
+        }
+        self.drawn = true;
+    }
+
+    fn end(&mut self) {
+        if let Some(start) = self.start.take() {
+            if !self.drawn {
+                self.output.line_to(point(start));
+            }
+        }
+    }
+}
+
+/// Pairs of parameters and lengths along `element`, from 0 to its length.
+fn measure(element: &Element) -> Vec<(f32, f32)> {
+    match *element {
+        Element::Line(a, b) => vec![(0.0, 0.0), (1.0, distance(a, b))],
+        Element::Curve(ref curve) => {
+            let mut points = vec![curve[0]];
+            flatten_curve(curve, DASH_TOLERANCE, &mut points);
+            let n = (points.len() - 1) as f32;
+            let mut length = 0.0;
+            let mut samples = vec![(0.0, 0.0)];
+            for (i, w) in points.windows(2).enumerate() {
+                length += distance(w[0], w[1]);
+                samples.push(((i + 1) as f32 / n, length));
+            }
+            samples
+        }
+    }
+}
+
+/// The parameter at length `s` along an element measured as `samples`.
+fn parameter(samples: &[(f32, f32)], s: f32) -> f32 {
+    for w in samples.windows(2) {
+        let ((t0, s0), (t1, s1)) = (w[0], w[1]);
+        if s <= s1 {
+            if s1 <= s0 {
+                return t0;
+            }
+            return t0 + (t1 - t0) * ((s - s0) / (s1 - s0)).max(0.0);
+        }
+    }
+    1.0
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+fn element_point(element: &Element, t: f32) -> [f32; 2] {
+    match *element {
+        Element::Line(a, b) => lerp(a, b, t),
+        Element::Curve(ref curve) => point_at(curve, t),
+    }
+}
+
+/// The part of `element` between the parameters `t0` and `t1`.
+fn part(element: &Element, t0: f32, t1: f32) -> Element {
+    match *element {
+        Element::Line(a, b) => Element::Line(lerp(a, b, t0), lerp(a, b, t1)),
+        Element::Curve(curve) => {
+            // Split at t1 and keep the first part, then split that at the
+            // corresponding parameter of t0 and keep the second part.
+            let first = split(&curve, t1).0;
+            let t0 = if t1 > 0.0 { t0 / t1 } else { 0.0 };
+            Element::Curve(split(&first, t0).1)
+        }
+    }
+}
+
+/// Split the cubic Bézier curve at `t` with de Casteljau's algorithm.
+fn split(c: &[[f32; 2]; 4], t: f32) -> ([[f32; 2]; 4], [[f32; 2]; 4]) {
+    let (p01, p12, p23) = (
+        lerp(c[0], c[1], t),
+        lerp(c[1], c[2], t),
+        lerp(c[2], c[3], t),
+    );
+    let (p012, p123) = (lerp(p01, p12, t), lerp(p12, p23, t));
+    let p = lerp(p012, p123, t);
+    ([c[0], p01, p012, p], [p, p123, p23, c[3]])
+}
+
+/// Builds the outline of strokes, one side of a polyline at a time.
+struct Outline<'a, T: LengthUnit + 'a> {
+    output: Path<T>,
+    style: &'a StrokeStyle<T>,
+    half_width: f32,
+    /// The current point of the outline, if a subpath is open.
+    current: Option<[f32; 2]>,
+}
+
+impl<'a, T: LengthUnit> Outline<'a, T> {
+    /// Outline an open polyline as one subpath: along the left side, around
+    /// the end cap, back along the right side and around the start cap.
+    fn open(&mut self, points: &[[f32; 2]]) {
+        let reversed: Vec<_> = points.iter().rev().cloned().collect();
+        self.side(points, false);
+        self.cap(points);
+        self.side(&reversed, false);
+        self.cap(&reversed);
+        self.close();
+    }
+
+    /// Outline a closed polyline as two subpaths in opposite directions,
+    /// one along each side.
+    fn closed(&mut self, points: &[[f32; 2]]) {
+        let reversed: Vec<_> = points.iter().rev().cloned().collect();
+        self.side(points, true);
+        self.close();
+        self.side(&reversed, true);
+        self.close();
+    }
+
+    /// Add the left side of the polyline with the joins between its lines,
+    /// for a closed polyline including the line back to the start.
+    fn side(&mut self, points: &[[f32; 2]], closed: bool) {
+        let n = points.len();
+        let lines = if closed { n } else { n - 1 };
+        let (directions, lengths): (Vec<_>, Vec<_>) = (0..lines)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % n]);
+                let length = distance(a, b);
+                ([(b[0] - a[0]) / length, (b[1] - a[1]) / length], length)
+            })
+            .unzip();
+        if closed {
+            for i in 0..n {
+                let before = (i + lines - 1) % lines;
+                self.join(
+                    points[i],
+                    (directions[before], lengths[before]),
+                    (directions[i], lengths[i]),
+                );
+            }
+        } else {
+            let offset = self.offset(points[0], directions[0]);
+            self.to(offset);
+            for i in 1..lines {
+                self.join(
+                    points[i],
+                    (directions[i - 1], lengths[i - 1]),
+                    (directions[i], lengths[i]),
+                );
+            }
+            let offset = self.offset(points[n - 1], directions[lines - 1]);
+            self.to(offset);
+        }
+    }
+
+    /// Join the lines before and after `p`, given as direction and length.
+    fn join(
+        &mut self,
+        p: [f32; 2],
+        before: ([f32; 2], f32),
+        after: ([f32; 2], f32),
+    ) {
+        let ((d0, length0), (d1, length1)) = (before, after);
+        let h = self.half_width;
+        let cross = d0[0] * d1[1] - d0[1] * d1[0];
+        let dot = d0[0] * d1[0] + d0[1] * d1[1];
+        let (a, b) = (self.offset(p, d0), self.offset(p, d1));
+        // The point where the offset lines meet is the sum of the normals
+        // scaled by h / (1 + cos α) for the turning angle α.
+        let (n0, n1) = (normal(d0), normal(d1));
+        let miter = || {
+            let scale = h / (1.0 + dot);
+            [
+                p[0] + (n0[0] + n1[0]) * scale,
+                p[1] + (n0[1] + n1[1]) * scale,
+            ]
+        };
+        if cross.abs() < 1e-6 && dot > 0.0 {
+            self.to(a);
+            self.to(b);
+        } else if cross > 0.0 {
+            // The side is on the inside of the turn.  Cut the offset lines
+            // where they meet if that is within both lines, otherwise go
+            // around the point, which fills the same area.
+            let overlap = h * cross / (1.0 + dot);
+            if overlap <= length0.min(length1) / 2.0 {
+                self.to(miter());
+            } else {
+                self.to(a);
+                self.to(p);
+                self.to(b);
+            }
+        } else {
+            self.to(a);
+            match self.style.join_style {
+                JoinStyle::Miter => {
+                    // The ratio of the miter length to the line width is
+                    // 1 / sin(φ / 2) for the angle φ between the lines.
+                    let ratio = (2.0 / (1.0 + dot)).sqrt();
+                    if 1.0 + dot > 0.0 && ratio <= self.style.miter_limit {
+                        self.to(miter());
+                    }
+                }
+                JoinStyle::Round => {
+                    let sweep =
+                        if cross < 0.0 { cross.atan2(dot) } else { -PI };
+                    self.arc(p, n0[1].atan2(n0[0]), sweep);
+                }
+                JoinStyle::Bevel => {}
+            }
+            self.to(b);
+        }
+    }
+
+    /// Add the cap at the end of `points`, from the left side to the right.
+    fn cap(&mut self, points: &[[f32; 2]]) {
+        let (p, before) = (points[points.len() - 1], points[points.len() - 2]);
+        let length = distance(before, p);
+        let d = [(p[0] - before[0]) / length, (p[1] - before[1]) / length];
+        let h = self.half_width;
+        let n = normal(d);
+        match self.style.cap_style {
+            CapStyle::Butt => {}
+            CapStyle::Round => self.arc(p, n[1].atan2(n[0]), -PI),
+            CapStyle::ProjectingSquare => {
+                let q = [p[0] + d[0] * h, p[1] + d[1] * h];
+                self.to([q[0] + n[0] * h, q[1] + n[1] * h]);
+                self.to([q[0] - n[0] * h, q[1] - n[1] * h]);
+            }
+        }
+        self.to([p[0] - n[0] * h, p[1] - n[1] * h]);
+    }
+
+    /// Add an arc around `center` from the current point at angle `start`,
+    /// turning by `sweep` radians, as Bézier curves of at most 90 degrees.
+    fn arc(&mut self, center: [f32; 2], start: f32, sweep: f32) {
+        let r = self.half_width;
+        let pieces = (sweep.abs() / (PI / 2.0)).ceil().max(1.0);
+        let angle = sweep / pieces;
+        let k = 4.0 / 3.0 * (angle / 4.0).tan() * r;
+        for i in 0..pieces as usize {
+            let a0 = start + angle * i as f32;
+            let a1 = a0 + angle;
+            let (s0, c0, s1, c1) = (a0.sin(), a0.cos(), a1.sin(), a1.cos());
+            let p0 = [center[0] + r * c0, center[1] + r * s0];
+            let p1 = [center[0] + r * c1, center[1] + r * s1];
+            self.output.curve_to(
+                point([p0[0] - k * s0, p0[1] + k * c0]),
+                point([p1[0] + k * s1, p1[1] - k * c1]),
+                point(p1),
+            );
+            self.current = Some(p1);
+        }
+    }
+
+    /// The point half a line width to the left of `p` on a line in
+    /// direction `d`.
+    fn offset(&self, p: [f32; 2], d: [f32; 2]) -> [f32; 2] {
+        let n = normal(d);
+        [p[0] + n[0] * self.half_width, p[1] + n[1] * self.half_width]
+    }
+
+    /// Continue the outline to `p`, or start a subpath there.
+    fn to(&mut self, p: [f32; 2]) {
+        match self.current {
+            None => self.output.move_to(point(p)),
+            Some(current) if current == p => return,
+            Some(_) => self.output.line_to(point(p)),
+        }
+        self.current = Some(p);
+    }
+
+    fn close(&mut self) {
+        if self.current.take().is_some() {
+            self.output.close();
+        }
+    }
+}
+
+/// The unit vector to the left of the unit vector `d`.
+fn normal(d: [f32; 2]) -> [f32; 2] {
+    [-d[1], d[0]]
+}
+
+#[cfg(test)]
+use geometry::{FillRule, Rect, Segment};
+#[cfg(test)]
+use units::Points;
+
+#[cfg(test)]
+fn polyline(points: &[(f32, f32)]) -> Path<Points> {
+    let mut path = Path::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let p = Point::new(pt!(x), pt!(y));
+        if i == 0 {
+            path.move_to(p);
+        } else {
+            path.line_to(p);
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+fn count_subpaths(path: &Path<Points>) -> usize {
+    let moves = path.segments().iter().filter(|s| match **s {
+        Segment::MoveTo(_) => true,
+        _ => false,
+    });
+    moves.count()
+}
+
+#[test]
+fn test_dashed() {
+    let line = polyline(&[(0.0, 0.0), (100.0, 0.0)]);
+    let dashed = line.dashed(&[pt!(10), pt!(5)], pt!(0));
+    assert_eq!(7, count_subpaths(&dashed));
+    assert_eq!(pt!(70), dashed.length());
+    let dashed = line.dashed(&[pt!(10), pt!(5)], pt!(5));
+    assert_eq!(pt!(65), dashed.length());
+    // An odd pattern repeats with dashes and gaps swapped.
+    assert_eq!(5, count_subpaths(&line.dashed(&[pt!(10)], pt!(0))));
+    // Zero-length dashes become dots.
+    let dots = line.dashed(&[pt!(0), pt!(10)], pt!(0));
+    assert_eq!(11, count_subpaths(&dots));
+    assert_eq!(line, line.dashed(&[pt!(-1), pt!(5)], pt!(0)));
+
+    // Dashes far along a line are placed without accumulated rounding
+    // errors, and patterns too fine to dash leave the line solid.
+    let long = polyline(&[(0.0, 0.0), (30000.0, 0.0)]);
+    let dashed = long.dashed(&[pt!(0.1), pt!(0.2)], pt!(0));
+    assert_eq!(100_000, count_subpaths(&dashed));
+    let last_start = dashed.segments()[dashed.segments().len() - 2];
+    match last_start {
+        Segment::MoveTo(p) => assert!((p.x.pt - 29999.7).abs() < 0.01),
+        _ => panic!("expected the start of the last dash"),
+    }
+    let fine = [pt!(0.00001), pt!(0.00001)];
+    assert_eq!(long, long.dashed(&fine, pt!(0)));
+
+    let mut square = Path::new();
+    square.rect(&Rect::new(pt!(0), pt!(0), pt!(10), pt!(10)));
+    let dashed = square.dashed(&[pt!(50)], pt!(0));
+    assert_eq!(Some(&Segment::Close), dashed.segments().last());
+
+    let mut circle = Path::new();
+    circle.circle(Point::new(pt!(0), pt!(0)), pt!(10));
+    let dashed = circle.dashed(&[pt!(10), pt!(10)], pt!(0));
+    let expected = 30.0 + (20.0 * PI - 60.0);
+    assert!((dashed.length().pt - expected).abs() < 0.05);
+    for segment in dashed.segments() {
+        if let Segment::CurveTo(_, _, p) = *segment {
+            assert!((p.x.pt.hypot(p.y.pt) - 10.0).abs() < 0.01);
+        }
+    }
+}
+
+#[test]
+fn test_stroke_outline_caps() {
+    let line = polyline(&[(0.0, 0.0), (100.0, 0.0)]);
+    let mut style = StrokeStyle::new(pt!(10));
+    let outline = line.stroke_outline(&style, pt!(0.1));
+    let bbox = Rect::new(pt!(0), pt!(-5), pt!(100), pt!(10));
+    assert_eq!(Some(bbox), outline.bounding_box());
+    let inside = |outline: &Path<Points>, x: f32, y: f32| {
+        outline.contains(Point::new(pt!(x), pt!(y)), FillRule::NonZero)
+    };
+    assert!(inside(&outline, 50.0, 4.0));
+    assert!(!inside(&outline, 50.0, 6.0));
+
+    style.set_line_cap_style(CapStyle::ProjectingSquare);
+    let outline = line.stroke_outline(&style, pt!(0.1));
+    let bbox = Rect::new(pt!(-5), pt!(-5), pt!(110), pt!(10));
+    assert_eq!(Some(bbox), outline.bounding_box());
+    assert!(inside(&outline, -4.9, 4.9));
+
+    style.set_line_cap_style(CapStyle::Round);
+    let outline = line.stroke_outline(&style, pt!(0.1));
+    let bbox = outline.bounding_box().unwrap();
+    assert!((bbox.min_x().pt + 5.0).abs() < 1e-3);
+    assert!((bbox.max_x().pt - 105.0).abs() < 1e-3);
+    assert!(inside(&outline, -4.9, 0.0));
+    assert!(!inside(&outline, -4.9, 4.9));
+
+    // A single point is only painted with round caps.
+    let dot = polyline(&[(0.0, 0.0), (0.0, 0.0)]);
+    assert!(inside(&dot.stroke_outline(&style, pt!(0.1)), 0.0, 4.9));
+    style.set_line_cap_style(CapStyle::Butt);
+    assert!(dot.stroke_outline(&style, pt!(0.1)).is_empty());
+}
+
+#[test]
+fn test_stroke_outline_joins() {
+    let corner = polyline(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+    let mut style = StrokeStyle::new(pt!(10));
+    let inside = |style: &StrokeStyle<Points>, x: f32, y: f32| {
+        let outline = corner.stroke_outline(style, pt!(0.1));
+        outline.contains(Point::new(pt!(x), pt!(y)), FillRule::NonZero)
+    };
+    assert!(inside(&style, 104.0, -4.0));
+    assert!(inside(&style, 96.0, 4.0));
+    assert!(!inside(&style, 94.0, 6.0));
+    style.set_miter_limit(1.2);
+    assert!(!inside(&style, 104.0, -4.0));
+    assert!(inside(&style, 102.0, -2.0));
+    style.set_miter_limit(10.0);
+    style.set_miter_limit(0.5);
+    style.set_miter_limit(::std::f32::NAN);
+    assert_eq!(10.0, style.miter_limit());
+    style.set_line_join_style(JoinStyle::Bevel);
+    assert!(!inside(&style, 104.0, -4.0));
+    style.set_line_join_style(JoinStyle::Round);
+    assert!(!inside(&style, 104.0, -4.0));
+    assert!(inside(&style, 103.0, -3.0));
+
+    // A closed square becomes a ring.
+    let mut square = Path::new();
+    square.rect(&Rect::new(pt!(0), pt!(0), pt!(100), pt!(100)));
+    style.set_line_join_style(JoinStyle::Miter);
+    let outline = square.stroke_outline(&style, pt!(0.1));
+    assert_eq!(2, count_subpaths(&outline));
+    let bbox = Rect::new(pt!(-5), pt!(-5), pt!(110), pt!(110));
+    assert_eq!(Some(bbox), outline.bounding_box());
+    let inside = |x: f32, y: f32| {
+        outline.contains(Point::new(pt!(x), pt!(y)), FillRule::NonZero)
+    };
+    assert!(inside(-4.0, -4.0));
+    assert!(inside(4.0, 50.0));
+    assert!(!inside(50.0, 50.0));
+}
+
+#[test]
+fn test_stroke_outline_dashed() {
+    let line = polyline(&[(0.0, 0.0), (100.0, 0.0)]);
+    let mut style = StrokeStyle::new(pt!(2));
+    style.set_dash(&[pt!(10), pt!(10)], pt!(0));
+    let outline = line.stroke_outline(&style, pt!(0.1));
+    assert_eq!(5, count_subpaths(&outline));
+    let inside = |x: f32| {
+        outline.contains(Point::new(pt!(x), pt!(0)), FillRule::NonZero)
+    };
+    assert!(inside(5.0));
+    assert!(!inside(15.0));
+    style.set_dash(&[], pt!(0));
+    assert_eq!(1, count_subpaths(&line.stroke_outline(&style, pt!(0.1))));
+}
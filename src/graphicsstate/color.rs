@@ -0,0 +1,1198 @@
+use resources::Resources;
+use serialize::{Name, Real, TextString};
+use std::error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::mem;
+use std::str::FromStr;
+use Pdf;
+
+use super::named_colors::CSS_COLORS;
+use super::pattern::{PaintType, Pattern};
+
+/// Color components are written with this many decimal places, which is
+/// more than enough to distinguish 16 bit per component values.
+const COMPONENT_PRECISION: usize = 5;
+
+/// Any color (or grayscale) value that this library can make PDF represent.
+///
+/// Color components are stored as floating point numbers, usually in the
+/// range from 0 to 1. Components outside of the range of a color space are
+/// clipped by the PDF viewer.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Color(ColorValue);
+
+/// The color space and components of a color, only built by the
+/// constructors of `Color` so that the components are checked.
+#[derive(Debug, PartialEq, Clone)]
+enum ColorValue {
+    Rgb {
+        red: f32,
+        green: f32,
+        blue: f32,
+    },
+    Gray {
+        gray: f32,
+    },
+    Cmyk {
+        cyan: f32,
+        magenta: f32,
+        yellow: f32,
+        key: f32,
+    },
+    Separation {
+        separation: Separation,
+        tint: f32,
+    },
+    DeviceN {
+        device_n: DeviceN,
+        tints: Vec<f32>,
+    },
+    ICCBased {
+        profile: IccProfile,
+        components: Vec<f32>,
+    },
+    Pattern {
+        pattern: Pattern,
+        color: Option<Box<Color>>,
+    },
+}
+
+/// Convert a component from the range 0 to 255 to the range 0 to 1.
+fn norm(c: u8) -> f32 {
+    f32::from(c) / 255.0
+}
+
+/// Check that all components are finite. NaN would make a color unequal
+/// to itself, and PDF has no infinite numbers.
+fn check_components(components: &[f32]) {
+    assert!(
+        components.iter().all(|c| c.is_finite()),
+        "Color components must be finite."
+    );
+}
+
+impl Color {
+    /// Return a color from a RGB colorspace.
+
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let white = Color::rgb(255, 255, 255);
+    /// let black = Color::rgb(0, 0, 0);
+    /// let red = Color::rgb(255, 0, 0);
+    /// let yellow = Color::rgb(255, 255, 0);
+    /// ````
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Color::rgb_f32(norm(red), norm(green), norm(blue))
+    }
+
+    /// Return a color from a RGB colorspace with components from 0 to 1.
+    ///
+    /// # Panics
+    /// Panics if a component is not finite.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let orange = Color::rgb_f32(1.0, 0.5, 0.0);
+    /// assert_eq!("1 0.5 0", orange.to_string());
+    /// ````
+    pub fn rgb_f32(red: f32, green: f32, blue: f32) -> Self {
+        check_components(&[red, green, blue]);
+        Color(ColorValue::Rgb { red, green, blue })
+    }
+
+    /// Return a grayscale color value.
+
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let white = Color::gray(255);
+    /// let gray = Color::gray(128);
+    /// ````
+    pub fn gray(gray: u8) -> Self {
+        Color::gray_f32(norm(gray))
+    }
+
+    /// Return a grayscale color value from 0 (black) to 1 (white).
+    ///
+    /// # Panics
+    /// Panics if `gray` is not finite.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let gray = Color::gray_f32(0.5);
+    /// assert_eq!("0.5", gray.to_string());
+    /// ````
+    pub fn gray_f32(gray: f32) -> Self {
+        check_components(&[gray]);
+        Color(ColorValue::Gray { gray })
+    }
+
+    /// Return a color from a CMYK colorspace.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let white = Color::cmyk(0, 0, 0, 0);
+    /// let rich_black = Color::cmyk(153, 102, 102, 255);
+    /// let cyan = Color::cmyk(255, 0, 0, 0);
+    /// ````
+    pub fn cmyk(cyan: u8, magenta: u8, yellow: u8, key: u8) -> Self {
+        Color::cmyk_f32(norm(cyan), norm(magenta), norm(yellow), norm(key))
+    }
+
+    /// Return a color from a CMYK colorspace with components from 0 to 1.
+    ///
+    /// # Panics
+    /// Panics if a component is not finite.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// let rich_black = Color::cmyk_f32(0.6, 0.4, 0.4, 1.0);
+    /// ````
+    pub fn cmyk_f32(cyan: f32, magenta: f32, yellow: f32, key: f32) -> Self {
+        check_components(&[cyan, magenta, yellow, key]);
+        Color(ColorValue::Cmyk {
+            cyan,
+            magenta,
+            yellow,
+            key,
+        })
+    }
+
+    /// Return a tint of a spot color. A tint of 0 means no colorant is
+    /// applied, 255 is the full colorant.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::{Color, Separation};
+    /// let pantone =
+    ///     Separation::new("PANTONE 185 C", Color::cmyk(0, 232, 207, 0));
+    /// let full = Color::separation(&pantone, 255);
+    /// let light = Color::separation(&pantone, 64);
+    /// ````
+    pub fn separation(separation: &Separation, tint: u8) -> Self {
+        Color::separation_f32(separation, norm(tint))
+    }
+
+    /// Return a tint of a spot color from 0 (no colorant) to 1 (full
+    /// colorant).
+    ///
+    /// # Panics
+    /// Panics if `tint` is not finite.
+    pub fn separation_f32(separation: &Separation, tint: f32) -> Self {
+        check_components(&[tint]);
+        Color(ColorValue::Separation {
+            separation: separation.clone(),
+            tint,
+        })
+    }
+
+    /// Return a color mixed from several colorants. There has to be one tint
+    /// for each colorant of `device_n`.
+    ///
+    /// # Panics
+    /// Panics if the number of tints does not match the number of colorants.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::{Color, DeviceN, Separation};
+    /// let duotone = DeviceN::new(&[
+    ///     Separation::new("PANTONE 185 C", Color::cmyk(0, 232, 207, 0)),
+    ///     Separation::new("Black", Color::cmyk(0, 0, 0, 255)),
+    /// ]);
+    /// let dark_red = Color::device_n(&duotone, &[255, 100]);
+    /// ````
+    pub fn device_n(device_n: &DeviceN, tints: &[u8]) -> Self {
+        let tints: Vec<f32> = tints.iter().map(|&t| norm(t)).collect();
+        Color::device_n_f32(device_n, &tints)
+    }
+
+    /// Return a color mixed from several colorants with tints from 0 to 1.
+    ///
+    /// # Panics
+    /// Panics if the number of tints does not match the number of colorants
+    /// or a tint is not finite.
+    pub fn device_n_f32(device_n: &DeviceN, tints: &[f32]) -> Self {
+        assert_eq!(device_n.colorants.len(), tints.len());
+        check_components(tints);
+        Color(ColorValue::DeviceN {
+            device_n: device_n.clone(),
+            tints: tints.to_vec(),
+        })
+    }
+
+    /// Return a color in the color space of an ICC profile. There has to be
+    /// one component for each component of the profile's color space, e.g.
+    /// three for an RGB profile.
+    ///
+    /// # Panics
+    /// Panics if the number of components does not match the profile.
+    ///
+    /// # Example
+    /// ````no_run
+    /// # use simple_pdf::graphicsstate::Color;
+    /// # use simple_pdf::Pdf;
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// let mut document = Pdf::create("example.pdf")?;
+    /// let profile = document.add_icc_profile(&std::fs::read("sRGB.icc")?)?;
+    /// let red = Color::icc(&profile, &[255, 0, 0]);
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn icc(profile: &IccProfile, components: &[u8]) -> Self {
+        let components: Vec<f32> =
+            components.iter().map(|&c| norm(c)).collect();
+        Color::icc_f32(profile, &components)
+    }
+
+    /// Return a color in the color space of an ICC profile with components
+    /// from 0 to 1. Wide gamut profiles can use the extra precision.
+    ///
+    /// # Panics
+    /// Panics if the number of components does not match the profile or a
+    /// component is not finite.
+    pub fn icc_f32(profile: &IccProfile, components: &[f32]) -> Self {
+        assert_eq!(usize::from(profile.n), components.len());
+        check_components(components);
+        Color(ColorValue::ICCBased {
+            profile: *profile,
+            components: components.to_vec(),
+        })
+    }
+
+    /// Return a RGB color from hue (in degrees), saturation and lightness
+    /// (from 0 to 1).
+    ///
+    /// # Panics
+    /// Panics if an argument is not finite.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// assert_eq!(Color::rgb(255, 0, 0), Color::hsl(0.0, 1.0, 0.5));
+    /// assert_eq!(Color::rgb_f32(0.5, 0.5, 0.5), Color::hsl(120.0, 0.0, 0.5));
+    /// ````
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Return a RGB color from hue (in degrees), saturation and value (from 0
+    /// to 1).
+    ///
+    /// # Panics
+    /// Panics if an argument is not finite.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// assert_eq!(Color::rgb(0, 0, 255), Color::hsv(240.0, 1.0, 1.0));
+    /// ````
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        Color::from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Return a RGB color from a hue, a chroma and the amount of white added
+    /// to all components.
+    fn from_hue(hue: f32, chroma: f32, min: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Color::rgb_f32(r + min, g + min, b + min)
+    }
+
+    /// Return hue (in degrees), saturation and lightness of a RGB or gray
+    /// color, or `None` for other colors.
+    pub fn to_hsl(&self) -> Option<(f32, f32, f32)> {
+        let (hue, chroma, max) = self.hue_chroma()?;
+        let min = max - chroma;
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness <= 0.0 || lightness >= 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        Some((hue, saturation, lightness))
+    }
+
+    /// Return hue (in degrees), saturation and value of a RGB or gray color,
+    /// or `None` for other colors.
+    pub fn to_hsv(&self) -> Option<(f32, f32, f32)> {
+        let (hue, chroma, max) = self.hue_chroma()?;
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+        Some((hue, saturation, max))
+    }
+
+    /// Return hue, chroma and the largest component of a RGB or gray color.
+    fn hue_chroma(&self) -> Option<(f32, f32, f32)> {
+        let (r, g, b) = match self.0 {
+            ColorValue::Rgb { red, green, blue } => (red, green, blue),
+            ColorValue::Gray { gray } => (gray, gray, gray),
+            _ => return None,
+        };
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        Some((hue, chroma, max))
+    }
+
+    /// Return a color from a hexadecimal string in the form `#RRGGBB` or
+    /// `#RGB`, or `None` if the string is malformed.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// assert_eq!(Some(Color::rgb(255, 136, 0)), Color::from_hex("#ff8800"));
+    /// assert_eq!(Some(Color::rgb(255, 136, 0)), Color::from_hex("#F80"));
+    /// assert_eq!(None, Color::from_hex("ff8800"));
+    /// ````
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.starts_with('#')
+            || !hex[1..].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        let digits = &hex[1..];
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        match digits.len() {
+            6 => Some(Color::from_u32(value)),
+            3 => {
+                let (r, g, b) = (value >> 8, (value >> 4) & 0xF, value & 0xF);
+                Some(Color::rgb(
+                    (r * 0x11) as u8,
+                    (g * 0x11) as u8,
+                    (b * 0x11) as u8,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Return a color by its CSS name, e.g. `"rebeccapurple"`, or `None` if
+    /// there is no such color. The name is not case sensitive.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::graphicsstate::Color;
+    /// assert_eq!(Some(Color::rgb(255, 165, 0)), Color::from_name("orange"));
+    /// assert_eq!(Some(Color::rgb(255, 165, 0)), Color::from_name("Orange"));
+    /// assert_eq!(None, Color::from_name("blurple"));
+    /// ````
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        CSS_COLORS
+            .binary_search_by_key(&name.as_str(), |&(n, _)| n)
+            .ok()
+            .map(|i| Color::from_u32(CSS_COLORS[i].1))
+    }
+
+    /// Return a RGB color from a value in the form `0xRRGGBB`.
+    fn from_u32(value: u32) -> Self {
+        Color::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+    }
+
+    /// Return a color that paints with a colored tiling pattern.
+    ///
+    /// # Panics
+    /// Panics if the pattern is uncolored.
+    pub fn pattern(pattern: Pattern) -> Self {
+        assert!(
+            pattern.paint_type() == PaintType::Colored,
+            "An uncolored pattern needs a color."
+        );
+        Color(ColorValue::Pattern {
+            pattern,
+            color: None,
+        })
+    }
+
+    /// Return a color that paints with an uncolored tiling pattern in the
+    /// given color.
+    ///
+    /// # Panics
+    /// Panics if the pattern is colored or `color` is a pattern.
+    pub fn uncolored_pattern(pattern: Pattern, color: Color) -> Self {
+        assert!(
+            pattern.paint_type() == PaintType::Uncolored,
+            "A colored pattern can't be painted in another color."
+        );
+        assert!(
+            !color.is_pattern(),
+            "An uncolored pattern can't be painted with a pattern."
+        );
+        Color(ColorValue::Pattern {
+            pattern,
+            color: Some(Box::new(color)),
+        })
+    }
+
+    /// The color space of a color that has to be declared in the resources
+    /// of a page. Device color spaces need no declaration.
+    pub(crate) fn color_space(&self) -> Option<ColorSpace> {
+        match self.0 {
+            ColorValue::Separation { ref separation, .. } => {
+                Some(ColorSpace::Separation(separation.clone()))
+            }
+            ColorValue::DeviceN { ref device_n, .. } => {
+                Some(ColorSpace::DeviceN(device_n.clone()))
+            }
+            ColorValue::ICCBased { profile, .. } => {
+                Some(ColorSpace::ICCBased(profile))
+            }
+            ColorValue::Pattern {
+                color: Some(ref color),
+                ..
+            } => Some(match color.color_space() {
+                Some(space) => ColorSpace::UncoloredPattern(Box::new(space)),
+                None => ColorSpace::DevicePattern(color.device_space()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Check whether two colors share the same color space, so they can be
+    /// interpolated.
+    pub(crate) fn same_color_space(&self, other: &Color) -> bool {
+        !self.is_pattern()
+            && mem::discriminant(&self.0) == mem::discriminant(&other.0)
+            && self.color_space() == other.color_space()
+    }
+
+    /// Return the operand for a `/ColorSpace` entry of a dictionary, either
+    /// the name of a device color space or a reference to the color space
+    /// object.
+    pub(crate) fn color_space_operand(&self, pdf: &mut Pdf) -> Result<String> {
+        match self.color_space() {
+            Some(color_space) => {
+                let object_id = pdf.color_space_object_id(color_space)?;
+                Ok(format!("{} 0 R", object_id))
+            }
+            None => Ok(format!("/{}", self.device_space())),
+        }
+    }
+
+    /// The name of the device color space of a device color.
+    fn device_space(&self) -> &'static str {
+        match self.0 {
+            ColorValue::Rgb { .. } => "DeviceRGB",
+            ColorValue::Gray { .. } => "DeviceGray",
+            ColorValue::Cmyk { .. } => "DeviceCMYK",
+            _ => panic!("Not a device color: {:?}", self),
+        }
+    }
+
+    /// The white point (no ink) in the device color space of this color.
+    fn white(&self) -> Color {
+        match self.0 {
+            ColorValue::Rgb { .. } => Color::rgb_f32(1.0, 1.0, 1.0),
+            ColorValue::Gray { .. } => Color::gray_f32(1.0),
+            ColorValue::Cmyk { .. } => Color::cmyk_f32(0.0, 0.0, 0.0, 0.0),
+            _ => panic!("Not a device color: {:?}", self),
+        }
+    }
+
+    fn is_pattern(&self) -> bool {
+        matches!(self.0, ColorValue::Pattern { .. })
+    }
+
+    fn is_device_color(&self) -> bool {
+        matches!(
+            self.0,
+            ColorValue::Rgb { .. }
+                | ColorValue::Gray { .. }
+                | ColorValue::Cmyk { .. }
+        )
+    }
+
+    /// Return the color components in the color space of this color.
+    pub(crate) fn components(&self) -> Vec<f32> {
+        match self.0 {
+            ColorValue::Rgb { red, green, blue } => vec![red, green, blue],
+            ColorValue::Gray { gray } => vec![gray],
+            ColorValue::Cmyk {
+                cyan,
+                magenta,
+                yellow,
+                key,
+            } => vec![cyan, magenta, yellow, key],
+            ColorValue::Separation { tint, .. } => vec![tint],
+            ColorValue::DeviceN { ref tints, .. } => tints.clone(),
+            ColorValue::ICCBased { ref components, .. } => components.clone(),
+            ColorValue::Pattern { ref color, .. } => {
+                color.as_ref().map_or_else(Vec::new, |c| c.components())
+            }
+        }
+    }
+}
+
+// The components can only be set by the constructors, which reject NaN, so
+// the derived PartialEq is an equivalence, which makes colors usable as part
+// of color spaces in hash maps.
+impl Eq for Color {}
+
+impl Hash for Color {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(&self.0).hash(state);
+        match self.0 {
+            ColorValue::Rgb { red, green, blue } => {
+                hash_components(&[red, green, blue], state)
+            }
+            ColorValue::Gray { gray } => hash_components(&[gray], state),
+            ColorValue::Cmyk {
+                cyan,
+                magenta,
+                yellow,
+                key,
+            } => hash_components(&[cyan, magenta, yellow, key], state),
+            ColorValue::Separation {
+                ref separation,
+                tint,
+            } => {
+                separation.hash(state);
+                hash_components(&[tint], state);
+            }
+            ColorValue::DeviceN {
+                ref device_n,
+                ref tints,
+            } => {
+                device_n.hash(state);
+                hash_components(tints, state);
+            }
+            ColorValue::ICCBased {
+                ref profile,
+                ref components,
+            } => {
+                profile.hash(state);
+                hash_components(components, state);
+            }
+            ColorValue::Pattern {
+                ref pattern,
+                ref color,
+            } => {
+                pattern.hash(state);
+                color.hash(state);
+            }
+        }
+    }
+}
+
+/// Hash the bit patterns of color components, with -0 as 0 since they are
+/// equal.
+fn hash_components<H: Hasher>(components: &[f32], state: &mut H) {
+    for &c in components {
+        let c = if c == 0.0 { 0.0 } else { c };
+        c.to_bits().hash(state);
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &c) in self.components().iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", Real(c, COMPONENT_PRECISION))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parse a color from a hexadecimal string (`#RRGGBB` or `#RGB`) or a
+    /// CSS color name.
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        Color::from_hex(s)
+            .or_else(|| Color::from_name(s))
+            .ok_or(ParseColorError(()))
+    }
+}
+
+/// The error returned when parsing a [Color](struct.Color.html) from a string
+/// fails.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseColorError(());
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid color, expected #RRGGBB, #RGB or a CSS color name"
+        )
+    }
+}
+
+impl error::Error for ParseColorError {}
+
+/// A spot color, i.e. a single colorant like a Pantone ink, as described in
+/// section 8.6.6.4 of the PDF specification.
+///
+/// Devices that cannot print the colorant use the alternate color instead.
+/// Tints of the spot color are mapped linearly between white and the
+/// alternate color.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Separation {
+    colorant: String,
+    alternate: Box<Color>,
+}
+
+impl Separation {
+    /// Create a spot color with the name of the colorant and its appearance
+    /// at full tint in a device color space (RGB, gray or CMYK).
+    ///
+    /// # Panics
+    /// Panics if `alternate` is not a device color.
+    pub fn new(colorant: &str, alternate: Color) -> Self {
+        assert!(
+            alternate.is_device_color(),
+            "The alternate color of a separation must be a device color."
+        );
+        Separation {
+            colorant: colorant.to_string(),
+            alternate: Box::new(alternate),
+        }
+    }
+
+    /// Get the name of the colorant.
+    pub fn colorant(&self) -> &str {
+        &self.colorant
+    }
+
+    /// Write the color space array.
+    fn write_color_space(&self, output: &mut dyn Write) -> Result<()> {
+        writeln!(
+            output,
+            "[/Separation {name} /{space}\n \
+             << /FunctionType 2 /Domain [0 1] /C0 [{c0}] /C1 [{c1}] /N 1 >>]",
+            name = Name(&self.colorant),
+            space = self.alternate.device_space(),
+            c0 = self.alternate.white(),
+            c1 = self.alternate
+        )
+    }
+}
+
+/// A color space mixing several colorants, as described in section 8.6.6.5
+/// of the PDF specification.
+///
+/// Devices that cannot print the colorants use their alternate colors, which
+/// all have to share the same device color space. Overlapping tints are
+/// mixed by adding the inks for CMYK and by multiplying the remaining light
+/// for RGB and gray.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DeviceN {
+    colorants: Vec<Separation>,
+}
+
+impl DeviceN {
+    /// Create a color space from a list of colorants.
+    ///
+    /// # Panics
+    /// Panics if `colorants` is empty or the alternate colors of the
+    /// colorants do not share the same device color space.
+    pub fn new(colorants: &[Separation]) -> Self {
+        assert!(
+            !colorants.is_empty(),
+            "DeviceN needs at least one colorant."
+        );
+        let space = colorants[0].alternate.device_space();
+        assert!(
+            colorants
+                .iter()
+                .all(|c| c.alternate.device_space() == space),
+            "The alternate colors of DeviceN must share one color space."
+        );
+        DeviceN {
+            colorants: colorants.to_vec(),
+        }
+    }
+
+    /// Get the colorants of the color space.
+    pub fn colorants(&self) -> &[Separation] {
+        &self.colorants
+    }
+
+    /// Return the PostScript calculator code of the tint transform. The input
+    /// operands are the tints of the colorants, the output operands the
+    /// components in the alternate color space.
+    fn tint_transform(&self) -> String {
+        let alternates: Vec<Vec<f32>> = self
+            .colorants
+            .iter()
+            .map(|c| c.alternate.components())
+            .collect();
+        let subtractive =
+            self.colorants[0].alternate.device_space() == "DeviceCMYK";
+        let n = alternates.len();
+        let m = alternates[0].len();
+        let mut code = String::from("{");
+        for j in 0..m {
+            // The stack holds the n tints and the j outputs computed so far
+            // plus the running value. The tint of colorant i is therefore
+            // at depth n - i + j.
+            if subtractive {
+                code.push_str(" 0");
+                for (i, alternate) in alternates.iter().enumerate() {
+                    code.push_str(&format!(
+                        " {} index {} mul add",
+                        n - i + j,
+                        Real(alternate[j], COMPONENT_PRECISION)
+                    ));
+                }
+                code.push_str(" dup 1 gt { pop 1 } if");
+            } else {
+                code.push_str(" 1");
+                for (i, alternate) in alternates.iter().enumerate() {
+                    code.push_str(&format!(
+                        " {} index {} mul 1 exch sub mul",
+                        n - i + j,
+                        Real(1.0 - alternate[j], COMPONENT_PRECISION)
+                    ));
+                }
+            }
+        }
+        // Move the outputs below the tints and drop the tints.
+        code.push_str(&format!(" {} {} roll", n + m, m));
+        for _ in 0..n {
+            code.push_str(" pop");
+        }
+        code.push_str(" }");
+        code
+    }
+
+    /// Write the tint transform function stream and return its object id.
+    fn write_tint_transform(&self, pdf: &mut Pdf) -> Result<usize> {
+        let code = self.tint_transform();
+        let m = match self.colorants[0].alternate.device_space() {
+            "DeviceGray" => 1,
+            "DeviceRGB" => 3,
+            _ => 4,
+        };
+        let domain = vec!["0 1"; self.colorants.len()].join(" ");
+        let range = vec!["0 1"; m].join(" ");
+        pdf.write_new_object(|object_id, pdf| {
+            writeln!(
+                pdf.output,
+                "<< /FunctionType 4 /Domain [{}] /Range [{}] /Length {} >>\n\
+                 stream\n\
+                 {}\n\
+                 endstream",
+                domain,
+                range,
+                code.len(),
+                code
+            )?;
+            Ok(object_id)
+        })
+    }
+}
+
+/// An ICC color profile embedded in a document, as described in section
+/// 8.6.5.5 of the PDF specification.
+///
+/// A profile is created with
+/// [Pdf::add_icc_profile](../struct.Pdf.html#method.add_icc_profile) and can
+/// only be used in the document that created it.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct IccProfile {
+    object_id: usize,
+    n: u8,
+}
+
+impl IccProfile {
+    /// Check the header of an ICC profile and return the number of color
+    /// components and the matching device color space.
+    fn parse_header(data: &[u8]) -> Result<(u8, &'static str)> {
+        if data.len() < 128 || &data[36..40] != b"acsp" {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Not an ICC profile.",
+            ));
+        }
+        let size = u32::from(data[0]) << 24
+            | u32::from(data[1]) << 16
+            | u32::from(data[2]) << 8
+            | u32::from(data[3]);
+        if size as usize != data.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The ICC profile size does not match its header.",
+            ));
+        }
+        match &data[16..20] {
+            b"GRAY" => Ok((1, "DeviceGray")),
+            b"RGB " => Ok((3, "DeviceRGB")),
+            b"CMYK" => Ok((4, "DeviceCMYK")),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Only gray, RGB and CMYK ICC profiles are supported.",
+            )),
+        }
+    }
+
+    /// Write the profile as a stream object to a pdf file.
+    pub(crate) fn write_object(pdf: &mut Pdf, data: &[u8]) -> Result<Self> {
+        let (n, alternate) = IccProfile::parse_header(data)?;
+        pdf.write_new_object(|object_id, pdf| {
+            writeln!(
+                pdf.output,
+                "<< /N {} /Alternate /{} /Length {} >>\n\
+                 stream",
+                n,
+                alternate,
+                data.len()
+            )?;
+            pdf.output.write_all(data)?;
+            writeln!(pdf.output, "\nendstream")?;
+            Ok(IccProfile { object_id, n })
+        })
+    }
+
+    /// Get the number of color components of the profile's color space.
+    pub fn components(&self) -> u8 {
+        self.n
+    }
+}
+
+/// The intended output device of a document, its printing condition
+/// described by an ICC profile, as described in section 14.11.5 of the PDF
+/// specification.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct OutputIntent {
+    subtype: OutputIntentSubtype,
+    identifier: String,
+    profile: IccProfile,
+}
+
+impl OutputIntent {
+    /// Create an output intent for the printing condition named by
+    /// `identifier`, e.g. "FOGRA39", characterized by `profile`.
+    pub fn new(
+        subtype: OutputIntentSubtype,
+        identifier: &str,
+        profile: &IccProfile,
+    ) -> Self {
+        OutputIntent {
+            subtype,
+            identifier: identifier.to_string(),
+            profile: *profile,
+        }
+    }
+
+    /// Write the output intent dictionary.
+    pub(crate) fn write_dictionary(
+        &self,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        writeln!(
+            output,
+            "<< /Type /OutputIntent /S /{} /OutputConditionIdentifier {} \
+             /DestOutputProfile {} 0 R >>",
+            self.subtype,
+            TextString(&self.identifier),
+            self.profile.object_id
+        )
+    }
+}
+
+/// The standard an output intent is defined for.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum OutputIntentSubtype {
+    /// PDF/X, for prepress exchange.
+    PDFX,
+    /// PDF/A, for archiving.
+    PDFA,
+}
+
+impl fmt::Display for OutputIntentSubtype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                OutputIntentSubtype::PDFX => "GTS_PDFX",
+                OutputIntentSubtype::PDFA => "GTS_PDFA1",
+            }
+        )
+    }
+}
+
+/// A color space that has to be declared in the resources of a page.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub(crate) enum ColorSpace {
+    Separation(Separation),
+    DeviceN(DeviceN),
+    ICCBased(IccProfile),
+    /// The space of uncolored patterns painted in a device color space.
+    DevicePattern(&'static str),
+    /// The space of uncolored patterns painted in another color space.
+    UncoloredPattern(Box<ColorSpace>),
+}
+
+impl ColorSpace {
+    /// Write the color space object(s) to a pdf file and return the id of
+    /// the color space object.
+    pub fn write_object(&self, pdf: &mut Pdf) -> Result<usize> {
+        match *self {
+            ColorSpace::Separation(ref separation) => {
+                pdf.write_new_object(|object_id, pdf| {
+                    separation.write_color_space(&mut pdf.output)?;
+                    Ok(object_id)
+                })
+            }
+            ColorSpace::DeviceN(ref device_n) => {
+                let function_id = device_n.write_tint_transform(pdf)?;
+                pdf.write_new_object(|object_id, pdf| {
+                    write!(pdf.output, "[/DeviceN [")?;
+                    for colorant in &device_n.colorants {
+                        write!(pdf.output, "{}", Name(&colorant.colorant))?;
+                    }
+                    writeln!(
+                        pdf.output,
+                        "] /{} {} 0 R]",
+                        device_n.colorants[0].alternate.device_space(),
+                        function_id
+                    )?;
+                    Ok(object_id)
+                })
+            }
+            ColorSpace::ICCBased(profile) => {
+                pdf.write_new_object(|object_id, pdf| {
+                    writeln!(
+                        pdf.output,
+                        "[/ICCBased {} 0 R]",
+                        profile.object_id
+                    )?;
+                    Ok(object_id)
+                })
+            }
+            ColorSpace::DevicePattern(space) => {
+                pdf.write_new_object(|object_id, pdf| {
+                    writeln!(pdf.output, "[/Pattern /{}]", space)?;
+                    Ok(object_id)
+                })
+            }
+            ColorSpace::UncoloredPattern(ref space) => {
+                let base_id = pdf.color_space_object_id((**space).clone())?;
+                pdf.write_new_object(|object_id, pdf| {
+                    writeln!(pdf.output, "[/Pattern {} 0 R]", base_id)?;
+                    Ok(object_id)
+                })
+            }
+        }
+    }
+}
+
+/// Write the operators setting `color` as stroking or non-stroking color.
+/// Color spaces that are not device color spaces are registered in
+/// `resources`.
+pub(crate) fn write_color(
+    output: &mut dyn Write,
+    resources: &mut Resources,
+    color: &Color,
+    stroke: bool,
+) -> Result<()> {
+    let (gray, rgb, cmyk, cs, scn) = if stroke {
+        ("G", "RG", "K", "CS", "SCN")
+    } else {
+        ("g", "rg", "k", "cs", "scn")
+    };
+    if let ColorValue::Pattern { pattern, .. } = color.0 {
+        let space = match color.color_space() {
+            Some(space) => resources.color_space_name(space),
+            None => "/Pattern".to_string(),
+        };
+        let name = resources.pattern_name(pattern.object_id);
+        // A colored pattern has no components, an uncolored one those of
+        // its color.
+        let components = color.to_string();
+        let separator = if components.is_empty() { "" } else { " " };
+        return writeln!(
+            output,
+            "{} {} {}{}{} {}",
+            space, cs, components, separator, name, scn
+        );
+    }
+    match color.color_space() {
+        Some(space) => {
+            let name = resources.color_space_name(space);
+            writeln!(output, "{} {} {} {}", name, cs, color, scn)
+        }
+        None => {
+            let operator = match color.0 {
+                ColorValue::Rgb { .. } => rgb,
+                ColorValue::Gray { .. } => gray,
+                _ => cmyk,
+            };
+            writeln!(output, "{} {}", color, operator)
+        }
+    }
+}
+
+#[test]
+fn test_write_color_operators() {
+    let mut resources = Resources::default();
+    let mut output = Vec::new();
+    let spot = Separation::new("Spot", Color::cmyk(0, 255, 0, 0));
+    write_color(&mut output, &mut resources, &Color::gray(0), false).unwrap();
+    write_color(
+        &mut output,
+        &mut resources,
+        &Color::cmyk(0, 255, 0, 0),
+        true,
+    )
+    .unwrap();
+    write_color(
+        &mut output,
+        &mut resources,
+        &Color::separation(&spot, 255),
+        false,
+    )
+    .unwrap();
+    write_color(
+        &mut output,
+        &mut resources,
+        &Color::separation(&spot, 0),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        "0 g\n0 1 0 0 K\n/CS0 cs 1 scn\n/CS0 CS 0 SCN\n",
+        String::from_utf8(output).unwrap()
+    );
+    assert_eq!(1, resources.color_spaces.len());
+}
+
+#[test]
+fn test_device_n_tint_transform() {
+    let device_n = DeviceN::new(&[
+        Separation::new("A", Color::gray(0)),
+        Separation::new("B", Color::gray(255)),
+    ]);
+    assert_eq!(
+        "{ 1 2 index 1 mul 1 exch sub mul 1 index 0 mul 1 exch sub mul \
+         3 1 roll pop pop }",
+        device_n.tint_transform()
+    );
+}
+
+#[test]
+fn test_icc_profile_header() {
+    let mut data = vec![0; 132];
+    data[3] = 132;
+    data[16..20].copy_from_slice(b"CMYK");
+    data[36..40].copy_from_slice(b"acsp");
+    assert_eq!((4, "DeviceCMYK"), IccProfile::parse_header(&data).unwrap());
+    data[16..20].copy_from_slice(b"Lab ");
+    assert!(IccProfile::parse_header(&data).is_err());
+    data[16..20].copy_from_slice(b"GRAY");
+    assert!(IccProfile::parse_header(&data[..128]).is_err());
+    data[36..40].copy_from_slice(b"acsq");
+    assert!(IccProfile::parse_header(&data).is_err());
+}
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(Ok(Color::rgb(0x66, 0x33, 0x99)), "#663399".parse());
+    assert_eq!(Ok(Color::rgb(0x66, 0x33, 0x99)), "RebeccaPurple".parse());
+    assert_eq!(Ok(Color::rgb(0xAA, 0xBB, 0xCC)), " #abc ".parse());
+    assert!("#abcd".parse::<Color>().is_err());
+    assert!("#+bcdef".parse::<Color>().is_err());
+    assert!("nocolor".parse::<Color>().is_err());
+}
+
+#[test]
+fn test_hsl_hsv_round_trip() {
+    let color = Color::rgb_f32(0.2, 0.4, 0.8);
+    let (h, s, l) = color.to_hsl().unwrap();
+    assert_eq!(color.to_string(), Color::hsl(h, s, l).to_string());
+    let (h, s, v) = color.to_hsv().unwrap();
+    assert_eq!(color.to_string(), Color::hsv(h, s, v).to_string());
+    assert_eq!(None, Color::cmyk(0, 0, 0, 0).to_hsl());
+}
+
+#[test]
+fn test_color_precision() {
+    assert_eq!("0.50196 1 0", Color::rgb(128, 255, 0).to_string());
+    assert_eq!(
+        "0.1 0.2 0.3 0.4",
+        Color::cmyk_f32(0.1, 0.2, 0.3, 0.4).to_string()
+    );
+}
+
+#[test]
+fn test_write_pattern_color() {
+    let mut resources = Resources::default();
+    let mut output = Vec::new();
+    let colored = Pattern::new(7, PaintType::Colored);
+    let uncolored = Pattern::new(8, PaintType::Uncolored);
+    write_color(&mut output, &mut resources, &Color::pattern(colored), false)
+        .unwrap();
+    write_color(
+        &mut output,
+        &mut resources,
+        &Color::uncolored_pattern(uncolored, Color::rgb(255, 0, 0)),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        "/Pattern cs /P0 scn\n/CS0 CS 1 0 0 /P1 SCN\n",
+        String::from_utf8(output).unwrap()
+    );
+    assert_eq!(
+        Some(ColorSpace::DevicePattern("DeviceGray")),
+        Color::uncolored_pattern(uncolored, Color::gray(0)).color_space()
+    );
+}
+
+#[test]
+fn test_color_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    let hash = |color: &Color| {
+        let mut hasher = DefaultHasher::new();
+        color.hash(&mut hasher);
+        hasher.finish()
+    };
+    let black = Color::gray_f32(0.0);
+    assert_eq!(black, Color::gray_f32(-0.0));
+    assert_eq!(hash(&black), hash(&Color::gray_f32(-0.0)));
+    assert_ne!(hash(&black), hash(&Color::gray_f32(0.5)));
+    assert_ne!(hash(&black), hash(&Color::rgb_f32(0.0, 0.0, 0.0)));
+}
+
+#[test]
+#[should_panic]
+fn test_nan_color_component() {
+    Color::rgb_f32(1.0, ::std::f32::NAN, 0.0);
+}
+
+#[test]
+#[should_panic]
+fn test_infinite_color_component() {
+    Color::hsv(::std::f32::INFINITY, 1.0, 1.0);
+}
@@ -0,0 +1,183 @@
+use serialize::Real;
+use std::fmt;
+
+/// Alpha values are written with this many decimal places.
+const ALPHA_PRECISION: usize = 4;
+
+/// Blend modes for combining transparent content with its backdrop, as
+/// described in section 11.3.5 of the PDF specification.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum BlendMode {
+    /// Paint the source over the backdrop.
+    Normal,
+    /// Multiply backdrop and source, the result is always at least as dark.
+    Multiply,
+    /// Multiply the complements of backdrop and source, the result is always
+    /// at least as light.
+    Screen,
+    /// Multiply or screen the colors, depending on the backdrop.
+    Overlay,
+    /// Select the darker of backdrop and source.
+    Darken,
+    /// Select the lighter of backdrop and source.
+    Lighten,
+    /// Brighten the backdrop to reflect the source.
+    ColorDodge,
+    /// Darken the backdrop to reflect the source.
+    ColorBurn,
+    /// Multiply or screen the colors, depending on the source.
+    HardLight,
+    /// Darken or lighten the colors, depending on the source.
+    SoftLight,
+    /// Subtract the darker of the two colors from the lighter one.
+    Difference,
+    /// Like `Difference`, but with lower contrast.
+    Exclusion,
+    /// Use the hue of the source with saturation and luminosity of the
+    /// backdrop.
+    Hue,
+    /// Use the saturation of the source with hue and luminosity of the
+    /// backdrop.
+    Saturation,
+    /// Use hue and saturation of the source with the luminosity of the
+    /// backdrop.
+    Color,
+    /// Use the luminosity of the source with hue and saturation of the
+    /// backdrop.
+    Luminosity,
+}
+
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                BlendMode::Normal => "Normal",
+                BlendMode::Multiply => "Multiply",
+                BlendMode::Screen => "Screen",
+                BlendMode::Overlay => "Overlay",
+                BlendMode::Darken => "Darken",
+                BlendMode::Lighten => "Lighten",
+                BlendMode::ColorDodge => "ColorDodge",
+                BlendMode::ColorBurn => "ColorBurn",
+                BlendMode::HardLight => "HardLight",
+                BlendMode::SoftLight => "SoftLight",
+                BlendMode::Difference => "Difference",
+                BlendMode::Exclusion => "Exclusion",
+                BlendMode::Hue => "Hue",
+                BlendMode::Saturation => "Saturation",
+                BlendMode::Color => "Color",
+                BlendMode::Luminosity => "Luminosity",
+            }
+        )
+    }
+}
+
+/// How the content of a soft mask determines the opacity, as described in
+/// section 11.6.5.2 of the PDF specification.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum SoftMaskType {
+    /// The luminosity of the mask content is used, so white is opaque and
+    /// black, including everything not painted, is transparent.
+    Luminosity,
+    /// The opacity of the mask content is used, so everything painted is
+    /// opaque and everything not painted is transparent.
+    Alpha,
+}
+
+impl fmt::Display for SoftMaskType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                SoftMaskType::Luminosity => "Luminosity",
+                SoftMaskType::Alpha => "Alpha",
+            }
+        )
+    }
+}
+
+/// The attributes of a transparency group, as described in section 11.4.8
+/// of the PDF specification.
+///
+/// The content of a group is composited on its own before the result is
+/// painted on the page.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub struct TransparencyGroup {
+    /// An isolated group is composited on a fully transparent backdrop
+    /// instead of the content beneath it.
+    pub isolated: bool,
+    /// In a knockout group, each element is composited with the group's
+    /// initial backdrop rather than with the elements painted before it.
+    pub knockout: bool,
+}
+
+impl fmt::Display for TransparencyGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<< /Type /Group /S /Transparency /I {} /K {} >>",
+            self.isolated, self.knockout
+        )
+    }
+}
+
+/// A graphics state parameter dictionary, as described in section 8.4.5 of
+/// the PDF specification.
+///
+/// Every ExtGState sets a single parameter. Its written dictionary is used
+/// to deduplicate the resources of a page and the objects of a document.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum ExtGState {
+    FillAlpha(f32),
+    StrokeAlpha(f32),
+    BlendMode(BlendMode),
+    /// A soft mask with the object id of its transparency group, or `None`
+    /// to remove the current soft mask.
+    SoftMask(Option<(SoftMaskType, usize)>),
+}
+
+impl fmt::Display for ExtGState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let clamp = |alpha: f32| Real(alpha.clamp(0.0, 1.0), ALPHA_PRECISION);
+        write!(f, "<< /Type /ExtGState ")?;
+        match *self {
+            ExtGState::FillAlpha(alpha) => write!(f, "/ca {}", clamp(alpha))?,
+            ExtGState::StrokeAlpha(alpha) => write!(f, "/CA {}", clamp(alpha))?,
+            ExtGState::BlendMode(mode) => write!(f, "/BM /{}", mode)?,
+            ExtGState::SoftMask(None) => write!(f, "/SMask /None")?,
+            ExtGState::SoftMask(Some((subtype, group_id))) => write!(
+                f,
+                "/SMask << /Type /Mask /S /{} /G {} 0 R >>",
+                subtype, group_id
+            )?,
+        }
+        write!(f, " >>")
+    }
+}
+
+#[test]
+fn test_ext_gstate_dictionary() {
+    assert_eq!(
+        "<< /Type /ExtGState /ca 0.5 >>",
+        ExtGState::FillAlpha(0.5).to_string()
+    );
+    assert_eq!(
+        "<< /Type /ExtGState /CA 1 >>",
+        ExtGState::StrokeAlpha(1.5).to_string()
+    );
+    assert_eq!(
+        "<< /Type /ExtGState /BM /ColorDodge >>",
+        ExtGState::BlendMode(BlendMode::ColorDodge).to_string()
+    );
+    assert_eq!(
+        "<< /Type /ExtGState /SMask << /Type /Mask /S /Alpha /G 7 0 R >> >>",
+        ExtGState::SoftMask(Some((SoftMaskType::Alpha, 7))).to_string()
+    );
+    assert_eq!(
+        "<< /Type /ExtGState /SMask /None >>",
+        ExtGState::SoftMask(None).to_string()
+    );
+}
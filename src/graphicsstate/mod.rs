@@ -1,9 +1,29 @@
 //! Types for representing details in the graphics state.
+use geometry::{Point, Rect, Vector};
 use std::f32::consts::PI;
 use std::fmt;
 use std::ops::Mul;
 use units::{LengthUnit, UserSpace};
 
+mod color;
+pub(crate) use self::color::{write_color, ColorSpace};
+pub use self::color::{
+    Color, DeviceN, IccProfile, OutputIntent, OutputIntentSubtype,
+    ParseColorError, Separation,
+};
+
+mod named_colors;
+
+mod extgstate;
+pub(crate) use self::extgstate::ExtGState;
+pub use self::extgstate::{BlendMode, SoftMaskType, TransparencyGroup};
+
+mod shading;
+pub use self::shading::{CoonsPatch, Gradient, MeshVertex, Shading};
+
+mod pattern;
+pub use self::pattern::{PaintType, Pattern, TilingPattern};
+
 /// Line join styles, as described in section 8.4.3.4 of the PDF specification.
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum JoinStyle {
@@ -56,55 +76,6 @@ impl fmt::Display for CapStyle {
     }
 }
 
-/// Any color (or grayscale) value that this library can make PDF represent.
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
-pub enum Color {
-    #[doc(hidden)]
-    RGB { red: u8, green: u8, blue: u8 },
-    #[doc(hidden)]
-    Gray { gray: u8 },
-}
-
-impl Color {
-    /// Return a color from a RGB colorspace.
-
-    /// # Example
-    /// ````
-    /// # use simple_pdf::graphicsstate::Color;
-    /// let white = Color::rgb(255, 255, 255);
-    /// let black = Color::rgb(0, 0, 0);
-    /// let red = Color::rgb(255, 0, 0);
-    /// let yellow = Color::rgb(255, 255, 0);
-    /// ````
-    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
-        Color::RGB { red, green, blue }
-    }
-
-    /// Return a grayscale color value.
-
-    /// # Example
-    /// ````
-    /// # use simple_pdf::graphicsstate::Color;
-    /// let white = Color::gray(255);
-    /// let gray = Color::gray(128);
-    /// ````
-    pub fn gray(gray: u8) -> Self {
-        Color::Gray { gray }
-    }
-}
-
-impl fmt::Display for Color {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let norm = |c: u8| f32::from(c) / 255.0;
-        match *self {
-            Color::RGB { red, green, blue } => {
-                write!(f, "{} {} {}", norm(red), norm(green), norm(blue))
-            }
-            Color::Gray { gray } => write!(f, "{}", norm(gray)),
-        }
-    }
-}
-
 /// A transformation matrix for the pdf graphics state.
 ///
 /// Matrixes can be created with numerous named constructors and combined by
@@ -181,6 +152,59 @@ impl Matrix {
             v: [1., alpha.tan(), beta.tan(), 1., 0., 0.],
         }
     }
+    /// Return the position of `point` after the transformation.
+    pub fn transform_point<T: LengthUnit>(&self, point: Point<T>) -> Point<T> {
+        let (x, y) = self.apply(point.x.pt, point.y.pt);
+        Point::new(UserSpace::from_pt(x), UserSpace::from_pt(y))
+    }
+    /// Return `vector` after the transformation, which is not affected by
+    /// the translation part of the matrix.
+    pub fn transform_vector<T: LengthUnit>(
+        &self,
+        vector: Vector<T>,
+    ) -> Vector<T> {
+        let v = self.v;
+        let (dx, dy) = (vector.dx.pt, vector.dy.pt);
+        Vector::new(
+            UserSpace::from_pt(v[0] * dx + v[2] * dy),
+            UserSpace::from_pt(v[1] * dx + v[3] * dy),
+        )
+    }
+    /// Return the bounding box of `rect` after the transformation.
+    pub fn transform_rect<T: LengthUnit>(&self, rect: &Rect<T>) -> Rect<T> {
+        let corners = rect.corners();
+        let first = self.transform_point(corners[0]);
+        corners[1..]
+            .iter()
+            .fold(Rect::from_points(first, first), |r, &p| {
+                let p = self.transform_point(p);
+                r.union(&Rect::from_points(p, p))
+            })
+    }
+    /// Return the matrix that reverses the transformation, or `None` if it
+    /// can't be reversed because it scales to zero in some direction.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let [a, b, c, d, e, f] = self.v;
+        let det = a * d - b * c;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        Some(Matrix {
+            v: [
+                d / det,
+                -b / det,
+                -c / det,
+                a / det,
+                (c * f - d * e) / det,
+                (b * e - a * f) / det,
+            ],
+        })
+    }
+    /// Apply the transformation to the point (x, y) in points.
+    pub(crate) fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let v = self.v;
+        (v[0] * x + v[2] * y + v[4], v[1] * x + v[3] * y + v[5])
+    }
 }
 
 impl fmt::Display for Matrix {
@@ -225,6 +249,29 @@ fn test_matrix_mul_d() {
     assert_unit(Matrix::rotate(PI) * Matrix::uniform_scale(-1.));
 }
 
+#[test]
+fn test_matrix_transform() {
+    use units::Points;
+    let m = Matrix::rotate_deg(90.) * Matrix::translate(pt!(10), pt!(0));
+    let p = m.transform_point(Point::new(pt!(1), pt!(2)));
+    assert!((p.x.pt - 8.).abs() < 1e-5 && (p.y.pt - 1.).abs() < 1e-5);
+    let v = m.transform_vector(Vector::new(pt!(1), pt!(0)));
+    assert!(v.dx.pt.abs() < 1e-5 && (v.dy.pt - 1.).abs() < 1e-5);
+    let r = Matrix::rotate_deg(45.).transform_rect(&Rect::new(
+        pt!(0),
+        pt!(0),
+        pt!(1),
+        pt!(1),
+    ));
+    let half_diagonal = 0.5f32.sqrt();
+    assert!((r.min_x().pt + half_diagonal).abs() < 1e-5);
+    assert!((r.max_x().pt - half_diagonal).abs() < 1e-5);
+    assert!((r.height().pt - 2. * half_diagonal).abs() < 1e-5);
+    assert_unit(m * m.inverse().unwrap());
+    assert_unit(m.inverse().unwrap() * m);
+    assert_eq!(None, Matrix::scale(1., 0.).inverse());
+}
+
 #[allow(dead_code)]
 fn assert_unit(m: Matrix) {
     assert_eq!(None, diff(&[1., 0., 0., 1., 0., 0.], &m.v));
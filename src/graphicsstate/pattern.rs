@@ -0,0 +1,161 @@
+use super::Matrix;
+use canvas::Canvas;
+use std::io::{Result, Write};
+use units::{LengthUnit, UserSpace};
+use Pdf;
+
+/// Whether the cell of a tiling pattern specifies its own colors.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PaintType {
+    /// The cell is drawn with its own colors.
+    Colored,
+    /// The cell is a stencil that is painted with the color given when the
+    /// pattern is used. Drawing the cell must not set any colors.
+    Uncolored,
+}
+
+/// The description of a tiling pattern, which fills an area by repeating a
+/// cell, as described in section 8.7.3 of the PDF specification.
+///
+/// The cell is drawn by
+/// [Pdf::create_tiling_pattern](../struct.Pdf.html#method.create_tiling_pattern)
+/// and the created [Pattern](struct.Pattern.html) is used as color with
+/// [Color::pattern](struct.Color.html#method.pattern) or
+/// [Color::uncolored_pattern](struct.Color.html#method.uncolored_pattern).
+///
+/// # Example
+/// ````
+/// # #[macro_use]
+/// # extern crate simple_pdf;
+/// # use simple_pdf::units::{Points, UserSpace};
+/// # use simple_pdf::Pdf;
+/// use simple_pdf::graphicsstate::{Color, PaintType, TilingPattern};
+/// # use std::io;
+/// # fn main() -> io::Result<()> {
+/// # let mut document = Pdf::create("foo.pdf")?;
+/// let cell =
+///     TilingPattern::new(PaintType::Uncolored, pt!(0), pt!(0), pt!(6), pt!(6));
+/// let dots = document.create_tiling_pattern(&cell, |canvas| {
+///     canvas.circle(pt!(3), pt!(3), pt!(1.5))?;
+///     canvas.fill()
+/// })?;
+/// document.render_page(pt!(100), pt!(100), |canvas| {
+///     let gray = Color::gray(80);
+///     canvas.set_fill_color(Color::uncolored_pattern(dots, gray))?;
+///     canvas.rectangle(pt!(10), pt!(10), pt!(80), pt!(80))?;
+///     canvas.fill()
+/// })?;
+/// # document.finish()
+/// # }
+/// ````
+#[derive(Debug, PartialEq, Clone)]
+pub struct TilingPattern {
+    paint_type: PaintType,
+    bbox: [f32; 4],
+    step: (f32, f32),
+    matrix: Option<Matrix>,
+}
+
+impl TilingPattern {
+    /// Create a pattern whose cell has a corner at (x, y) and extends width
+    /// × height. The cells are repeated without gaps, unless a step is set
+    /// with [set_step](#method.set_step).
+    pub fn new<T: LengthUnit>(
+        paint_type: PaintType,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Self {
+        TilingPattern {
+            paint_type,
+            bbox: [x.pt, y.pt, (x + width).pt, (y + height).pt],
+            step: (width.pt, height.pt),
+            matrix: None,
+        }
+    }
+
+    /// Set the horizontal and vertical distance between cells.
+    pub fn set_step<T: LengthUnit>(
+        &mut self,
+        x_step: UserSpace<T>,
+        y_step: UserSpace<T>,
+    ) {
+        self.step = (x_step.pt, y_step.pt);
+    }
+
+    /// Set the matrix mapping the pattern space to the default coordinate
+    /// space of the page, e.g. to rotate or scale the pattern.
+    pub fn set_matrix(&mut self, matrix: Matrix) {
+        self.matrix = Some(matrix);
+    }
+
+    /// Draw the cell with a nested canvas and write the pattern object.
+    pub(crate) fn write_object<F>(
+        &self,
+        pdf: &mut Pdf,
+        render_cell: F,
+    ) -> Result<Pattern>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+    {
+        let (content, resources) = pdf.render_content(render_cell)?;
+        let resource_oids = pdf.write_resources(resources)?;
+        let paint_type = self.paint_type;
+        let object_id = pdf.write_new_object(|object_id, pdf| {
+            write!(
+                pdf.output,
+                "<< /Type /Pattern /PatternType 1 /PaintType {} /TilingType 1\n   \
+                 /BBox [{} {} {} {}] /XStep {} /YStep {}\n   ",
+                match paint_type {
+                    PaintType::Colored => 1,
+                    PaintType::Uncolored => 2,
+                },
+                self.bbox[0],
+                self.bbox[1],
+                self.bbox[2],
+                self.bbox[3],
+                self.step.0,
+                self.step.1
+            )?;
+            if let Some(matrix) = self.matrix {
+                write!(pdf.output, "/Matrix [{}]\n   ", matrix)?;
+            }
+            writeln!(
+                pdf.output,
+                "/Resources {}\n   \
+                 /Length {}\n\
+                 >>\n\
+                 stream",
+                resource_oids,
+                content.len()
+            )?;
+            pdf.output.write_all(&content)?;
+            writeln!(pdf.output, "endstream")?;
+            Ok(object_id)
+        })?;
+        Ok(Pattern::new(object_id, paint_type))
+    }
+}
+
+/// A tiling pattern that is part of a document, created with
+/// [Pdf::create_tiling_pattern](../struct.Pdf.html#method.create_tiling_pattern).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Pattern {
+    pub(crate) object_id: usize,
+    paint_type: PaintType,
+}
+
+impl Pattern {
+    pub(crate) fn new(object_id: usize, paint_type: PaintType) -> Self {
+        Pattern {
+            object_id,
+            paint_type,
+        }
+    }
+
+    /// Get the paint type of the pattern.
+    pub fn paint_type(&self) -> PaintType {
+        self.paint_type
+    }
+}
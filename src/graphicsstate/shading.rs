@@ -0,0 +1,461 @@
+use super::Color;
+use std::io::Result;
+use units::{LengthUnit, UserSpace};
+use Pdf;
+
+/// A color gradient, made of color stops at positions from 0 to 1.
+///
+/// Between two stops, the colors are interpolated linearly. Before the first
+/// and after the last stop, the color of the nearest stop is used.
+///
+/// # Example
+/// ````
+/// # use simple_pdf::graphicsstate::{Color, Gradient};
+/// let sunset = Gradient::new(&[
+///     (0.0, Color::rgb(255, 200, 0)),
+///     (0.6, Color::rgb(255, 80, 0)),
+///     (1.0, Color::rgb(80, 0, 120)),
+/// ]);
+/// ````
+#[derive(Debug, PartialEq, Clone)]
+pub struct Gradient {
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// Create a gradient from color stops given as pairs of position and
+    /// color.
+    ///
+    /// # Panics
+    /// Panics if there are less than two stops, the positions are not in
+    /// increasing order from 0 to 1 or the colors do not share one color
+    /// space.
+    pub fn new(stops: &[(f32, Color)]) -> Self {
+        assert!(stops.len() >= 2, "A gradient needs at least two stops.");
+        assert!(
+            stops.windows(2).all(|s| s[0].0 <= s[1].0)
+                && stops[0].0 >= 0.0
+                && stops[stops.len() - 1].0 <= 1.0,
+            "The positions of gradient stops must increase from 0 to 1."
+        );
+        assert!(
+            stops.iter().all(|s| s.1.same_color_space(&stops[0].1)),
+            "The colors of a gradient must share one color space."
+        );
+        Gradient {
+            stops: stops.to_vec(),
+        }
+    }
+
+    /// Create a gradient between two colors.
+    pub fn two_colors(start: Color, end: Color) -> Self {
+        Gradient::new(&[(0.0, start), (1.0, end)])
+    }
+
+    /// Return the function dictionary mapping a position from 0 to 1 to a
+    /// color, as described in section 7.10 of the PDF specification.
+    ///
+    /// Two stops are interpolated by an exponential (Type 2) function, more
+    /// stops by a stitching (Type 3) function of exponential functions.
+    fn function(&self) -> String {
+        let interpolate = |c0: &Color, c1: &Color| {
+            format!(
+                "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+                c0, c1
+            )
+        };
+        let mut stops = self.stops.clone();
+        if stops[0].0 > 0.0 {
+            let first = stops[0].1.clone();
+            stops.insert(0, (0.0, first));
+        }
+        if stops[stops.len() - 1].0 < 1.0 {
+            let last = stops[stops.len() - 1].1.clone();
+            stops.push((1.0, last));
+        }
+        if stops.len() == 2 {
+            return interpolate(&stops[0].1, &stops[1].1);
+        }
+        let functions: Vec<String> = stops
+            .windows(2)
+            .map(|s| interpolate(&s[0].1, &s[1].1))
+            .collect();
+        let bounds: Vec<String> = stops[1..stops.len() - 1]
+            .iter()
+            .map(|s| s.0.to_string())
+            .collect();
+        let encode = vec!["0 1"; functions.len()];
+        format!(
+            "<< /FunctionType 3 /Domain [0 1]\n   \
+             /Functions [{}]\n   \
+             /Bounds [{}] /Encode [{}] >>",
+            functions.join("\n   "),
+            bounds.join(" "),
+            encode.join(" ")
+        )
+    }
+}
+
+/// A smooth transition of colors that can fill an area, as described in
+/// section 8.7.4.5 of the PDF specification.
+///
+/// Shadings are painted with
+/// [Canvas::shade](../struct.Canvas.html#method.shade) or used as fill
+/// color with
+/// [Canvas::set_fill_shading](../struct.Canvas.html#method.set_fill_shading).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Shading {
+    kind: ShadingKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum ShadingKind {
+    Axial {
+        coords: [f32; 4],
+        gradient: Gradient,
+        extend: [bool; 2],
+    },
+    Radial {
+        coords: [f32; 6],
+        gradient: Gradient,
+        extend: [bool; 2],
+    },
+    FreeForm(Vec<[MeshVertex; 3]>),
+    CoonsPatch(Vec<CoonsPatch>),
+}
+
+/// A point of a mesh shading with its color.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MeshVertex {
+    x: f32,
+    y: f32,
+    color: Color,
+}
+
+impl MeshVertex {
+    /// Create a vertex at (x, y) with the given color.
+    pub fn new<T: LengthUnit>(
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        color: Color,
+    ) -> Self {
+        MeshVertex {
+            x: x.pt,
+            y: y.pt,
+            color,
+        }
+    }
+}
+
+/// A patch of a Coons patch mesh, bounded by four cubic Bézier curves and
+/// colored by interpolating the colors of its corners.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CoonsPatch {
+    points: [(f32, f32); 12],
+    colors: [Color; 4],
+}
+
+impl CoonsPatch {
+    /// Create a patch from the twelve control points of its boundary and the
+    /// colors of its four corners.
+    ///
+    /// The points go around the boundary starting at a corner, three per
+    /// side: a corner, followed by the two control points of the curve to
+    /// the next corner. The colors belong to the corners in the same order,
+    /// i.e. to the points 0, 3, 6 and 9.
+    pub fn new<T: LengthUnit>(
+        points: [(UserSpace<T>, UserSpace<T>); 12],
+        colors: [Color; 4],
+    ) -> Self {
+        let mut pt = [(0.0, 0.0); 12];
+        for (p, (x, y)) in pt.iter_mut().zip(points.iter()) {
+            *p = (x.pt, y.pt);
+        }
+        CoonsPatch { points: pt, colors }
+    }
+
+    /// Create a rectangular patch at (x, y) with straight sides. The colors
+    /// belong to the lower left, lower right, upper right and upper left
+    /// corner.
+    pub fn rectangle<T: LengthUnit>(
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        colors: [Color; 4],
+    ) -> Self {
+        let (x, y, w, h) = (x.pt, y.pt, width.pt, height.pt);
+        let mut points = [(0.0, 0.0); 12];
+        let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+        for i in 0..4 {
+            let (x0, y0) = corners[i];
+            let (x1, y1) = corners[(i + 1) % 4];
+            points[i * 3] = (x0, y0);
+            points[i * 3 + 1] = (x0 + (x1 - x0) / 3.0, y0 + (y1 - y0) / 3.0);
+            points[i * 3 + 2] =
+                (x0 + (x1 - x0) * 2.0 / 3.0, y0 + (y1 - y0) * 2.0 / 3.0);
+        }
+        CoonsPatch { points, colors }
+    }
+}
+
+impl Shading {
+    /// Create a linear gradient along the axis from (x0, y0) to (x1, y1).
+    pub fn axial<T: LengthUnit>(
+        x0: UserSpace<T>,
+        y0: UserSpace<T>,
+        x1: UserSpace<T>,
+        y1: UserSpace<T>,
+        gradient: &Gradient,
+    ) -> Self {
+        Shading {
+            kind: ShadingKind::Axial {
+                coords: [x0.pt, y0.pt, x1.pt, y1.pt],
+                gradient: gradient.clone(),
+                extend: [true, true],
+            },
+        }
+    }
+
+    /// Create a radial gradient between the start circle around (x0, y0)
+    /// with radius r0 and the end circle around (x1, y1) with radius r1.
+    pub fn radial<T: LengthUnit>(
+        x0: UserSpace<T>,
+        y0: UserSpace<T>,
+        r0: UserSpace<T>,
+        x1: UserSpace<T>,
+        y1: UserSpace<T>,
+        r1: UserSpace<T>,
+        gradient: &Gradient,
+    ) -> Self {
+        Shading {
+            kind: ShadingKind::Radial {
+                coords: [x0.pt, y0.pt, r0.pt, x1.pt, y1.pt, r1.pt],
+                gradient: gradient.clone(),
+                extend: [true, true],
+            },
+        }
+    }
+
+    /// Create a free-form mesh of triangles (Type 4 shading), each vertex
+    /// with its own color. The colors are interpolated over each triangle.
+    ///
+    /// # Panics
+    /// Panics if there are no triangles or the colors do not share one color
+    /// space.
+    pub fn triangles(triangles: &[[MeshVertex; 3]]) -> Self {
+        assert!(!triangles.is_empty(), "A mesh needs at least one triangle.");
+        let first = &triangles[0][0].color;
+        assert!(
+            triangles
+                .iter()
+                .flat_map(|t| t.iter())
+                .all(|v| v.color.same_color_space(first)),
+            "The colors of a mesh must share one color space."
+        );
+        Shading {
+            kind: ShadingKind::FreeForm(triangles.to_vec()),
+        }
+    }
+
+    /// Create a mesh of Coons patches (Type 6 shading).
+    ///
+    /// # Panics
+    /// Panics if there are no patches or the colors do not share one color
+    /// space.
+    pub fn coons_patches(patches: &[CoonsPatch]) -> Self {
+        assert!(!patches.is_empty(), "A mesh needs at least one patch.");
+        let first = &patches[0].colors[0];
+        assert!(
+            patches
+                .iter()
+                .flat_map(|p| p.colors.iter())
+                .all(|c| c.same_color_space(first)),
+            "The colors of a mesh must share one color space."
+        );
+        Shading {
+            kind: ShadingKind::CoonsPatch(patches.to_vec()),
+        }
+    }
+
+    /// Set whether an axial or radial shading is extended beyond its start
+    /// and end with the colors of the first and last stop. Both are
+    /// extended by default.
+    pub fn set_extend(&mut self, start: bool, end: bool) {
+        match self.kind {
+            ShadingKind::Axial { ref mut extend, .. }
+            | ShadingKind::Radial { ref mut extend, .. } => {
+                *extend = [start, end]
+            }
+            ShadingKind::FreeForm(_) | ShadingKind::CoonsPatch(_) => {}
+        }
+    }
+
+    /// Write the shading object to a pdf file and return its object id.
+    /// Equal shadings share one object.
+    pub(crate) fn write_object(&self, pdf: &mut Pdf) -> Result<usize> {
+        let (shading_type, coords, gradient, extend) = match self.kind {
+            ShadingKind::Axial {
+                ref coords,
+                ref gradient,
+                extend,
+            } => (2, &coords[..], gradient, extend),
+            ShadingKind::Radial {
+                ref coords,
+                ref gradient,
+                extend,
+            } => (3, &coords[..], gradient, extend),
+            ShadingKind::FreeForm(ref triangles) => {
+                let vertices = triangles.iter().flat_map(|t| {
+                    t.iter().map(|v| (0, vec![(v.x, v.y)], vec![&v.color]))
+                });
+                return write_mesh(pdf, 4, vertices);
+            }
+            ShadingKind::CoonsPatch(ref patches) => {
+                let patches = patches
+                    .iter()
+                    .map(|p| (0, p.points.to_vec(), p.colors.iter().collect()));
+                return write_mesh(pdf, 6, patches);
+            }
+        };
+        let color_space = gradient.stops[0].1.color_space_operand(pdf)?;
+        let coords: Vec<String> =
+            coords.iter().map(|c| c.to_string()).collect();
+        let dictionary = format!(
+            "<< /ShadingType {} /ColorSpace {}\n   \
+             /Coords [{}] /Extend [{} {}]\n   \
+             /Function {}\n\
+             >>",
+            shading_type,
+            color_space,
+            coords.join(" "),
+            extend[0],
+            extend[1],
+            gradient.function()
+        );
+        pdf.dictionary_object_id(dictionary)
+    }
+}
+
+/// Write a mesh shading stream. Each element of the mesh is given by its edge
+/// flag, its coordinates and its colors.
+///
+/// Coordinates are encoded with 32 bits and color components with 16 bits,
+/// scaled to the ranges given in the `/Decode` array.
+fn write_mesh<'a, I>(
+    pdf: &mut Pdf,
+    shading_type: u8,
+    elements: I,
+) -> Result<usize>
+where
+    I: Iterator<Item = (u8, Vec<(f32, f32)>, Vec<&'a Color>)> + Clone,
+{
+    let first_color = elements.clone().next().unwrap().2[0];
+    let color_space = first_color.color_space_operand(pdf)?;
+    let (x_range, y_range, data) = mesh_data(elements);
+    let decode: Vec<&str> = vec!["0 1"; first_color.components().len()];
+    let dictionary = format!(
+        "<< /ShadingType {} /ColorSpace {}\n   \
+         /BitsPerCoordinate 32 /BitsPerComponent 16 /BitsPerFlag 8\n   \
+         /Decode [{} {} {} {} {}]\n   \
+         /Length {}\n\
+         >>",
+        shading_type,
+        color_space,
+        x_range.0,
+        x_range.1,
+        y_range.0,
+        y_range.1,
+        decode.join(" "),
+        data.len()
+    );
+    pdf.stream_object_id(dictionary, data)
+}
+
+/// Encode the elements of a mesh, returning the ranges of the x and y
+/// coordinates and the encoded data.
+fn mesh_data<'a, I>(elements: I) -> ((f32, f32), (f32, f32), Vec<u8>)
+where
+    I: Iterator<Item = (u8, Vec<(f32, f32)>, Vec<&'a Color>)> + Clone,
+{
+    let (mut x_range, mut y_range) =
+        ((f32::MAX, f32::MIN), (f32::MAX, f32::MIN));
+    for (_, points, _) in elements.clone() {
+        for (x, y) in points {
+            x_range = (x_range.0.min(x), x_range.1.max(x));
+            y_range = (y_range.0.min(y), y_range.1.max(y));
+        }
+    }
+    // A range must not be empty, or the coordinates can't be decoded.
+    for range in [&mut x_range, &mut y_range].iter_mut() {
+        if range.1 <= range.0 {
+            range.1 = range.0 + 1.0;
+        }
+    }
+    let encode = |value: f32, (min, max): (f32, f32)| {
+        let scaled = f64::from(value - min) / f64::from(max - min);
+        (scaled * f64::from(u32::MAX)).round() as u32
+    };
+    let mut data = Vec::new();
+    for (flag, points, colors) in elements {
+        data.push(flag);
+        for (x, y) in points {
+            data.extend_from_slice(&encode(x, x_range).to_be_bytes());
+            data.extend_from_slice(&encode(y, y_range).to_be_bytes());
+        }
+        for color in colors {
+            for c in color.components() {
+                let c = (c.clamp(0.0, 1.0) * 65535.0).round() as u16;
+                data.extend_from_slice(&c.to_be_bytes());
+            }
+        }
+    }
+    (x_range, y_range, data)
+}
+
+#[test]
+fn test_gradient_function() {
+    let two = Gradient::two_colors(Color::gray(0), Color::gray(255));
+    assert_eq!(
+        "<< /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>",
+        two.function()
+    );
+    let stitched = Gradient::new(&[
+        (0.25, Color::gray(0)),
+        (0.5, Color::gray(255)),
+        (1.0, Color::gray(0)),
+    ]);
+    assert_eq!(
+        "<< /FunctionType 3 /Domain [0 1]\n   \
+         /Functions [<< /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [0] /N 1 >>\n   \
+         << /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>\n   \
+         << /FunctionType 2 /Domain [0 1] /C0 [1] /C1 [0] /N 1 >>]\n   \
+         /Bounds [0.25 0.5] /Encode [0 1 0 1 0 1] >>",
+        stitched.function()
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_gradient_mixed_color_spaces() {
+    Gradient::new(&[(0.0, Color::gray(0)), (1.0, Color::rgb(0, 0, 0))]);
+}
+
+#[test]
+fn test_mesh_data() {
+    let (red, blue) = (Color::rgb(255, 0, 0), Color::rgb(0, 0, 255));
+    let elements = vec![
+        (0, vec![(10.0, 5.0)], vec![&red]),
+        (0, vec![(20.0, 5.0)], vec![&blue]),
+    ];
+    let (x_range, y_range, data) = mesh_data(elements.into_iter());
+    assert_eq!((10.0, 20.0), x_range);
+    assert_eq!((5.0, 6.0), y_range);
+    assert_eq!(
+        vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 0, 0, 0, 0, //
+            0, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255,
+        ],
+        data
+    );
+}
@@ -0,0 +1,693 @@
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Result, Write};
+
+/// The compression of the pixel data of an image, as described in section
+/// 7.4 of the PDF specification.
+///
+/// JBIG2 compression is not offered, as it needs an encoder for which no
+/// pure Rust implementation is available. CCITT Group 4 is the alternative
+/// for bilevel images.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum ImageFilter {
+    /// Deflate compression of the pixel data.
+    Flate,
+    /// Deflate compression of the pixel data after applying PNG predictors,
+    /// which works well for photos and gradients.
+    FlatePredictor,
+    /// Run length encoding, which works well for images with large areas of
+    /// one color.
+    RunLength,
+    /// CCITT Group 4 fax encoding, which works well for scanned text. Only
+    /// for images with one component of 1 bit, like stencil masks.
+    CCITTGroup4,
+    /// The filter that results in the smallest data, or no filter if none
+    /// makes the data smaller.
+    Smallest,
+}
+
+/// The dimensions of the pixel data to encode.
+pub(crate) struct Layout {
+    pub width: usize,
+    pub height: usize,
+    pub components: usize,
+    pub bits_per_component: usize,
+}
+
+impl Layout {
+    fn row_bytes(&self) -> usize {
+        (self.width * self.components * self.bits_per_component).div_ceil(8)
+    }
+}
+
+/// Pixel data encoded with a filter.
+pub(crate) struct Encoded {
+    pub filter: Option<&'static str>,
+    pub decode_parms: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Encode pixel data with `filter`. Returns `None` if the filter can't
+/// encode the data.
+pub(crate) fn encode(
+    filter: ImageFilter,
+    layout: &Layout,
+    data: &[u8],
+) -> Result<Option<Encoded>> {
+    let encoded = match filter {
+        ImageFilter::Flate => Encoded {
+            filter: Some("FlateDecode"),
+            decode_parms: None,
+            data: deflate(data)?,
+        },
+        ImageFilter::FlatePredictor => Encoded {
+            filter: Some("FlateDecode"),
+            decode_parms: Some(format!(
+                "<< /Predictor 15 /Colors {} /BitsPerComponent {} \
+                 /Columns {} >>",
+                layout.components, layout.bits_per_component, layout.width
+            )),
+            data: deflate(&png_predict(layout, data))?,
+        },
+        ImageFilter::RunLength => Encoded {
+            filter: Some("RunLengthDecode"),
+            decode_parms: None,
+            data: run_length(data),
+        },
+        ImageFilter::CCITTGroup4 => {
+            if layout.components != 1 || layout.bits_per_component != 1 {
+                return Ok(None);
+            }
+            Encoded {
+                filter: Some("CCITTFaxDecode"),
+                decode_parms: Some(format!(
+                    "<< /K -1 /Columns {} /Rows {} >>",
+                    layout.width, layout.height
+                )),
+                data: ccitt_group4(layout, data),
+            }
+        }
+        ImageFilter::Smallest => {
+            let mut smallest = Encoded {
+                filter: None,
+                decode_parms: None,
+                data: data.to_vec(),
+            };
+            for &filter in &[
+                ImageFilter::Flate,
+                ImageFilter::FlatePredictor,
+                ImageFilter::RunLength,
+                ImageFilter::CCITTGroup4,
+            ] {
+                if let Some(encoded) = encode(filter, layout, data)? {
+                    if encoded.data.len() < smallest.data.len() {
+                        smallest = encoded;
+                    }
+                }
+            }
+            smallest
+        }
+    };
+    Ok(Some(encoded))
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Apply a PNG filter to each row, choosing the filter with the smallest
+/// sum of absolute differences as recommended by the PNG specification.
+fn png_predict(layout: &Layout, data: &[u8]) -> Vec<u8> {
+    let row_bytes = layout.row_bytes();
+    let bpp = (layout.components * layout.bits_per_component)
+        .div_ceil(8)
+        .max(1);
+    let mut result = Vec::with_capacity(data.len() + layout.height);
+    let zeros = vec![0; row_bytes];
+    let mut candidates: Vec<Vec<u8>> =
+        (0..5).map(|_| Vec::with_capacity(row_bytes + 1)).collect();
+    for y in 0..layout.height {
+        let row = &data[y * row_bytes..(y + 1) * row_bytes];
+        let previous = if y > 0 {
+            &data[(y - 1) * row_bytes..y * row_bytes]
+        } else {
+            &zeros[..]
+        };
+        for (filter, candidate) in candidates.iter_mut().enumerate() {
+            candidate.clear();
+            candidate.push(filter as u8);
+            for x in 0..row_bytes {
+                let a = if x >= bpp { row[x - bpp] } else { 0 };
+                let b = previous[x];
+                let c = if x >= bpp { previous[x - bpp] } else { 0 };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                candidate.push(row[x].wrapping_sub(predictor));
+            }
+        }
+        let best = candidates
+            .iter()
+            .min_by_key(|c| {
+                c[1..]
+                    .iter()
+                    .map(|&v| u32::from((v as i8).unsigned_abs()))
+                    .sum::<u32>()
+            })
+            .unwrap();
+        result.extend_from_slice(best);
+    }
+    result
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let pa = (p - i16::from(a)).abs();
+    let pb = (p - i16::from(b)).abs();
+    let pc = (p - i16::from(c)).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Encode data for the RunLengthDecode filter, as runs of up to 128 equal
+/// bytes or up to 128 literal bytes.
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len() + data.len() / 128 + 2);
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..]
+            .iter()
+            .take(128)
+            .take_while(|&&b| b == data[i])
+            .count();
+        // Runs of two bytes are only worth it outside of literals.
+        if run >= 3 || (run == 2 && literal_start == i) {
+            write_literal(&mut result, &data[literal_start..i]);
+            result.push((257 - run) as u8);
+            result.push(data[i]);
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    write_literal(&mut result, &data[literal_start..]);
+    result.push(128);
+    result
+}
+
+fn write_literal(result: &mut Vec<u8>, literal: &[u8]) {
+    for chunk in literal.chunks(128) {
+        result.push((chunk.len() - 1) as u8);
+        result.extend_from_slice(chunk);
+    }
+}
+
+/// Writes codes of up to 16 bits, most significant bit first.
+struct BitWriter {
+    data: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, length: u32) {
+        self.bits = self.bits << length | u32::from(code);
+        self.count += length;
+        while self.count >= 8 {
+            self.count -= 8;
+            self.data.push((self.bits >> self.count) as u8);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            let padding = 8 - self.count;
+            self.write(0, padding);
+        }
+        self.data
+    }
+}
+
+/// Encode 1 bit pixel data with CCITT Group 4 (ITU-T T.6) fax encoding.
+///
+/// The fax encoding calls pixels with the value 0 black, which decode to 0
+/// again with the default parameters of the CCITTFaxDecode filter.
+fn ccitt_group4(layout: &Layout, data: &[u8]) -> Vec<u8> {
+    let width = layout.width;
+    let row_bytes = layout.row_bytes();
+    let mut writer = BitWriter {
+        data: Vec::new(),
+        bits: 0,
+        count: 0,
+    };
+    // The reference line above the first row is white.
+    let mut reference = vec![false; width];
+    let mut coding = vec![false; width];
+    for y in 0..layout.height {
+        let row = &data[y * row_bytes..(y + 1) * row_bytes];
+        for (x, black) in coding.iter_mut().enumerate() {
+            *black = row[x / 8] & (0x80 >> (x % 8)) == 0;
+        }
+        encode_row(&mut writer, &reference, &coding);
+        reference.copy_from_slice(&coding);
+    }
+    // End of facsimile block: two EOL codes.
+    writer.write(1, 12);
+    writer.write(1, 12);
+    writer.finish()
+}
+
+/// Return the position of the first changing element after `start`, i.e. a
+/// pixel with a different color than the previous one. The pixel before
+/// the row is white; the end of the row is returned if there is none.
+fn next_change(line: &[bool], start: isize) -> usize {
+    let start = (start + 1).max(0) as usize;
+    (start..line.len())
+        .find(|&x| line[x] != (x > 0 && line[x - 1]))
+        .unwrap_or(line.len())
+}
+
+/// Encode one row with the two-dimensional coding relative to the row
+/// above.
+fn encode_row(writer: &mut BitWriter, reference: &[bool], coding: &[bool]) {
+    let width = coding.len();
+    let mut a0: isize = -1;
+    let mut black = false;
+    while a0 < width as isize {
+        let a1 = next_change(coding, a0);
+        // b1 is the first changing element on the reference line after a0
+        // with the opposite color of a0.
+        let mut b1 = next_change(reference, a0);
+        if b1 < width && reference[b1] == black {
+            b1 = next_change(reference, b1 as isize);
+        }
+        let b2 = next_change(reference, b1 as isize);
+        if b2 < a1 {
+            // Pass mode
+            writer.write(0b0001, 4);
+            a0 = b2 as isize;
+        } else if (a1 as isize - b1 as isize).abs() <= 3 {
+            let (code, length) = match a1 as isize - b1 as isize {
+                0 => (0b1, 1),
+                1 => (0b011, 3),
+                2 => (0b00_0011, 6),
+                3 => (0b000_0011, 7),
+                -1 => (0b010, 3),
+                -2 => (0b00_0010, 6),
+                _ => (0b000_0010, 7),
+            };
+            writer.write(code, length);
+            a0 = a1 as isize;
+            black = !black;
+        } else {
+            // Horizontal mode
+            let a2 = next_change(coding, a1 as isize);
+            writer.write(0b001, 3);
+            write_run(writer, a1 - a0.max(0) as usize, black);
+            write_run(writer, a2 - a1, !black);
+            a0 = a2 as isize;
+        }
+    }
+}
+
+/// Write the codes of a run of white or black pixels.
+fn write_run(writer: &mut BitWriter, mut run: usize, black: bool) {
+    while run >= 2560 {
+        let (code, length) = EXTENDED_MAKEUP_CODES[12];
+        writer.write(code, length);
+        run -= 2560;
+    }
+    if run >= 64 {
+        let makeup = run / 64;
+        let (code, length) = if makeup > 27 {
+            EXTENDED_MAKEUP_CODES[makeup - 28]
+        } else if black {
+            BLACK_MAKEUP_CODES[makeup - 1]
+        } else {
+            WHITE_MAKEUP_CODES[makeup - 1]
+        };
+        writer.write(code, length);
+        run %= 64;
+    }
+    let (code, length) = if black {
+        BLACK_TERMINATING_CODES[run]
+    } else {
+        WHITE_TERMINATING_CODES[run]
+    };
+    writer.write(code, length);
+}
+
+/// The codes for white runs of 0 to 63 pixels.
+const WHITE_TERMINATING_CODES: [(u16, u32); 64] = [
+    (0b0011_0101, 8),
+    (0b00_0111, 6),
+    (0b0111, 4),
+    (0b1000, 4),
+    (0b1011, 4),
+    (0b1100, 4),
+    (0b1110, 4),
+    (0b1111, 4),
+    (0b1_0011, 5),
+    (0b1_0100, 5),
+    (0b0_0111, 5),
+    (0b0_1000, 5),
+    (0b00_1000, 6),
+    (0b00_0011, 6),
+    (0b11_0100, 6),
+    (0b11_0101, 6),
+    (0b10_1010, 6),
+    (0b10_1011, 6),
+    (0b010_0111, 7),
+    (0b000_1100, 7),
+    (0b000_1000, 7),
+    (0b001_0111, 7),
+    (0b000_0011, 7),
+    (0b000_0100, 7),
+    (0b010_1000, 7),
+    (0b010_1011, 7),
+    (0b001_0011, 7),
+    (0b010_0100, 7),
+    (0b001_1000, 7),
+    (0b0000_0010, 8),
+    (0b0000_0011, 8),
+    (0b0001_1010, 8),
+    (0b0001_1011, 8),
+    (0b0001_0010, 8),
+    (0b0001_0011, 8),
+    (0b0001_0100, 8),
+    (0b0001_0101, 8),
+    (0b0001_0110, 8),
+    (0b0001_0111, 8),
+    (0b0010_1000, 8),
+    (0b0010_1001, 8),
+    (0b0010_1010, 8),
+    (0b0010_1011, 8),
+    (0b0010_1100, 8),
+    (0b0010_1101, 8),
+    (0b0000_0100, 8),
+    (0b0000_0101, 8),
+    (0b0000_1010, 8),
+    (0b0000_1011, 8),
+    (0b0101_0010, 8),
+    (0b0101_0011, 8),
+    (0b0101_0100, 8),
+    (0b0101_0101, 8),
+    (0b0010_0100, 8),
+    (0b0010_0101, 8),
+    (0b0101_1000, 8),
+    (0b0101_1001, 8),
+    (0b0101_1010, 8),
+    (0b0101_1011, 8),
+    (0b0100_1010, 8),
+    (0b0100_1011, 8),
+    (0b0011_0010, 8),
+    (0b0011_0011, 8),
+    (0b0011_0100, 8),
+];
+
+/// The codes for black runs of 0 to 63 pixels.
+const BLACK_TERMINATING_CODES: [(u16, u32); 64] = [
+    (0b00_0011_0111, 10),
+    (0b010, 3),
+    (0b11, 2),
+    (0b10, 2),
+    (0b011, 3),
+    (0b0011, 4),
+    (0b0010, 4),
+    (0b0_0011, 5),
+    (0b00_0101, 6),
+    (0b00_0100, 6),
+    (0b000_0100, 7),
+    (0b000_0101, 7),
+    (0b000_0111, 7),
+    (0b0000_0100, 8),
+    (0b0000_0111, 8),
+    (0b0_0001_1000, 9),
+    (0b00_0001_0111, 10),
+    (0b00_0001_1000, 10),
+    (0b00_0000_1000, 10),
+    (0b000_0110_0111, 11),
+    (0b000_0110_1000, 11),
+    (0b000_0110_1100, 11),
+    (0b000_0011_0111, 11),
+    (0b000_0010_1000, 11),
+    (0b000_0001_0111, 11),
+    (0b000_0001_1000, 11),
+    (0b0000_1100_1010, 12),
+    (0b0000_1100_1011, 12),
+    (0b0000_1100_1100, 12),
+    (0b0000_1100_1101, 12),
+    (0b0000_0110_1000, 12),
+    (0b0000_0110_1001, 12),
+    (0b0000_0110_1010, 12),
+    (0b0000_0110_1011, 12),
+    (0b0000_1101_0010, 12),
+    (0b0000_1101_0011, 12),
+    (0b0000_1101_0100, 12),
+    (0b0000_1101_0101, 12),
+    (0b0000_1101_0110, 12),
+    (0b0000_1101_0111, 12),
+    (0b0000_0110_1100, 12),
+    (0b0000_0110_1101, 12),
+    (0b0000_1101_1010, 12),
+    (0b0000_1101_1011, 12),
+    (0b0000_0101_0100, 12),
+    (0b0000_0101_0101, 12),
+    (0b0000_0101_0110, 12),
+    (0b0000_0101_0111, 12),
+    (0b0000_0110_0100, 12),
+    (0b0000_0110_0101, 12),
+    (0b0000_0101_0010, 12),
+    (0b0000_0101_0011, 12),
+    (0b0000_0010_0100, 12),
+    (0b0000_0011_0111, 12),
+    (0b0000_0011_1000, 12),
+    (0b0000_0010_0111, 12),
+    (0b0000_0010_1000, 12),
+    (0b0000_0101_1000, 12),
+    (0b0000_0101_1001, 12),
+    (0b0000_0010_1011, 12),
+    (0b0000_0010_1100, 12),
+    (0b0000_0101_1010, 12),
+    (0b0000_0110_0110, 12),
+    (0b0000_0110_0111, 12),
+];
+
+/// The codes for white runs of 64 to 1728 pixels in steps of 64.
+const WHITE_MAKEUP_CODES: [(u16, u32); 27] = [
+    (0b1_1011, 5),
+    (0b1_0010, 5),
+    (0b01_0111, 6),
+    (0b011_0111, 7),
+    (0b0011_0110, 8),
+    (0b0011_0111, 8),
+    (0b0110_0100, 8),
+    (0b0110_0101, 8),
+    (0b0110_1000, 8),
+    (0b0110_0111, 8),
+    (0b0_1100_1100, 9),
+    (0b0_1100_1101, 9),
+    (0b0_1101_0010, 9),
+    (0b0_1101_0011, 9),
+    (0b0_1101_0100, 9),
+    (0b0_1101_0101, 9),
+    (0b0_1101_0110, 9),
+    (0b0_1101_0111, 9),
+    (0b0_1101_1000, 9),
+    (0b0_1101_1001, 9),
+    (0b0_1101_1010, 9),
+    (0b0_1101_1011, 9),
+    (0b0_1001_1000, 9),
+    (0b0_1001_1001, 9),
+    (0b0_1001_1010, 9),
+    (0b01_1000, 6),
+    (0b0_1001_1011, 9),
+];
+
+/// The codes for black runs of 64 to 1728 pixels in steps of 64.
+const BLACK_MAKEUP_CODES: [(u16, u32); 27] = [
+    (0b00_0000_1111, 10),
+    (0b0000_1100_1000, 12),
+    (0b0000_1100_1001, 12),
+    (0b0000_0101_1011, 12),
+    (0b0000_0011_0011, 12),
+    (0b0000_0011_0100, 12),
+    (0b0000_0011_0101, 12),
+    (0b0_0000_0110_1100, 13),
+    (0b0_0000_0110_1101, 13),
+    (0b0_0000_0100_1010, 13),
+    (0b0_0000_0100_1011, 13),
+    (0b0_0000_0100_1100, 13),
+    (0b0_0000_0100_1101, 13),
+    (0b0_0000_0111_0010, 13),
+    (0b0_0000_0111_0011, 13),
+    (0b0_0000_0111_0100, 13),
+    (0b0_0000_0111_0101, 13),
+    (0b0_0000_0111_0110, 13),
+    (0b0_0000_0111_0111, 13),
+    (0b0_0000_0101_0010, 13),
+    (0b0_0000_0101_0011, 13),
+    (0b0_0000_0101_0100, 13),
+    (0b0_0000_0101_0101, 13),
+    (0b0_0000_0101_1010, 13),
+    (0b0_0000_0101_1011, 13),
+    (0b0_0000_0110_0100, 13),
+    (0b0_0000_0110_0101, 13),
+];
+
+/// The codes for runs of 1792 to 2560 pixels in steps of 64, shared by both
+/// colors.
+const EXTENDED_MAKEUP_CODES: [(u16, u32); 13] = [
+    (0b000_0000_1000, 11),
+    (0b000_0000_1100, 11),
+    (0b000_0000_1101, 11),
+    (0b0000_0001_0010, 12),
+    (0b0000_0001_0011, 12),
+    (0b0000_0001_0100, 12),
+    (0b0000_0001_0101, 12),
+    (0b0000_0001_0110, 12),
+    (0b0000_0001_0111, 12),
+    (0b0000_0001_1100, 12),
+    (0b0000_0001_1101, 12),
+    (0b0000_0001_1110, 12),
+    (0b0000_0001_1111, 12),
+];
+
+#[test]
+fn test_run_length() {
+    assert_eq!(vec![128], run_length(&[]));
+    assert_eq!(vec![253, 7, 128], run_length(&[7, 7, 7, 7]));
+    assert_eq!(
+        vec![1, 1, 2, 254, 3, 0, 4, 128],
+        run_length(&[1, 2, 3, 3, 3, 4])
+    );
+    let long = run_length(&[9; 300]);
+    assert_eq!(vec![129, 9, 129, 9, 213, 9, 128], long);
+}
+
+#[test]
+fn test_png_predict() {
+    let layout = Layout {
+        width: 4,
+        height: 2,
+        components: 1,
+        bits_per_component: 8,
+    };
+    // A horizontal ramp is best predicted by the left neighbor, the second
+    // equal row by the row above.
+    assert_eq!(
+        vec![1, 10, 10, 10, 10, 2, 0, 0, 0, 0],
+        png_predict(&layout, &[10, 20, 30, 40, 10, 20, 30, 40])
+    );
+}
+
+#[test]
+fn test_ccitt_group4() {
+    let layout = Layout {
+        width: 8,
+        height: 2,
+        components: 1,
+        bits_per_component: 1,
+    };
+    // An all white image is coded with vertical mode for the end of each
+    // row, followed by the end of facsimile block.
+    assert_eq!(
+        vec![0b1100_0000, 0b0000_0100, 0b0000_0000, 0b0100_0000],
+        ccitt_group4(&layout, &[0xFF, 0xFF])
+    );
+}
+
+#[test]
+fn test_ccitt_group4_modes() {
+    let layout = Layout {
+        width: 8,
+        height: 1,
+        components: 1,
+        bits_per_component: 1,
+    };
+    // A black run of 3 pixels after 2 white ones is coded in horizontal
+    // mode (001, white 2: 0111, black 3: 10), and the end of the row in
+    // vertical mode V0 (1).
+    assert_eq!(
+        vec![
+            0b0010_1111,
+            0b0100_0000,
+            0b0000_0100,
+            0b0000_0000,
+            0b0100_0000
+        ],
+        ccitt_group4(&layout, &[0b1100_0111])
+    );
+
+    // Below a row with a black run of 2 pixels, a white row passes the run
+    // in pass mode (0001) before V0 ends the row.
+    let layout = Layout {
+        height: 2,
+        ..layout
+    };
+    assert_eq!(
+        vec![
+            0b0010_1111,
+            0b1100_0110,
+            0b0000_0000,
+            0b0010_0000,
+            0b0000_0010
+        ],
+        ccitt_group4(&layout, &[0b1100_1111, 0b1111_1111])
+    );
+}
+
+#[test]
+fn test_ccitt_group4_long_runs() {
+    let layout = Layout {
+        width: 2700,
+        height: 1,
+        components: 1,
+        bits_per_component: 1,
+    };
+    // 2600 white pixels followed by 100 black ones, coded in horizontal
+    // mode (001) with the white run as the extended makeup code for 2560
+    // (000000011111) and the terminating code for 40 (00101001), and the
+    // black run as the makeup code for 64 (0000001111) and the
+    // terminating code for 36 (000011010100).
+    let mut row = vec![0xFF; 325];
+    row.extend_from_slice(&[0x00; 12]);
+    row.push(0x0F);
+    assert_eq!(
+        vec![0x20, 0x3E, 0x52, 0x07, 0x86, 0xA0, 0x00, 0x80, 0x08],
+        ccitt_group4(&layout, &row)
+    );
+}
+
+#[test]
+fn test_codes_are_prefix_free() {
+    for &(terminating, makeup) in &[
+        (&WHITE_TERMINATING_CODES, &WHITE_MAKEUP_CODES),
+        (&BLACK_TERMINATING_CODES, &BLACK_MAKEUP_CODES),
+    ] {
+        let codes: Vec<_> = terminating
+            .iter()
+            .chain(makeup.iter())
+            .chain(EXTENDED_MAKEUP_CODES.iter())
+            .collect();
+        for (i, &&(a, a_len)) in codes.iter().enumerate() {
+            for &&(b, b_len) in &codes[i + 1..] {
+                let len = a_len.min(b_len);
+                assert_ne!(a >> (a_len - len), b >> (b_len - len));
+            }
+        }
+    }
+}
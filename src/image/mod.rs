@@ -0,0 +1,606 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::ops::Deref;
+use std::sync::Arc;
+use Pdf;
+
+mod filter;
+pub use self::filter::ImageFilter;
+mod png;
+
+/// A raster image that can be placed on a canvas, written as an image
+/// XObject as described in section 8.9.5 of the PDF specification.
+///
+/// Images are written once per document, no matter how often they are
+/// drawn. Cloning an image does not copy its data.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    color_space: String,
+    bits_per_component: u8,
+    decode: Option<String>,
+    interpolate: bool,
+    /// Whether the image is a stencil mask painted with the fill color.
+    image_mask: bool,
+    filter: Option<&'static str>,
+    decode_parms: Option<String>,
+    /// The ranges of colors that are masked out.
+    mask: Option<String>,
+    smask: Option<Arc<Image>>,
+    data: ImageData,
+}
+
+/// The encoded data of an image, shared between clones, with a digest that
+/// is computed once so that hashing and comparing images is cheap.
+#[derive(Debug, Clone, Default)]
+struct ImageData {
+    bytes: Arc<Vec<u8>>,
+    digest: u64,
+}
+
+impl ImageData {
+    fn new(bytes: Vec<u8>) -> ImageData {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        ImageData {
+            bytes: Arc::new(bytes),
+            digest: hasher.finish(),
+        }
+    }
+}
+
+impl Deref for ImageData {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl PartialEq for ImageData {
+    fn eq(&self, other: &ImageData) -> bool {
+        self.digest == other.digest
+            && (Arc::ptr_eq(&self.bytes, &other.bytes)
+                || self.bytes == other.bytes)
+    }
+}
+
+impl Eq for ImageData {}
+
+impl Hash for ImageData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.digest.hash(state);
+    }
+}
+
+impl Image {
+    /// Create an image from the contents of a JPEG file. The data is
+    /// embedded as is and decoded by the PDF viewer.
+    ///
+    /// Returns an error of kind `InvalidData` if `data` is not a baseline
+    /// or progressive JPEG with 8 bits per component and 1 (gray), 3 (RGB)
+    /// or 4 (CMYK) components.
+    ///
+    /// # Example
+    /// ````no_run
+    /// # use simple_pdf::Image;
+    /// # use std::fs;
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// let photo = Image::jpeg(&fs::read("photo.jpg")?)?;
+    /// println!("{} × {} pixels", photo.width(), photo.height());
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn jpeg(data: &[u8]) -> Result<Image> {
+        let header = JpegHeader::parse(data)?;
+        let color_space = match header.components {
+            1 => "/DeviceGray",
+            3 => "/DeviceRGB",
+            4 => "/DeviceCMYK",
+            n => return Err(invalid(&format!("{} components in JPEG", n))),
+        };
+        if header.precision != 8 {
+            return Err(invalid(&format!(
+                "{} bit JPEG, only 8 bit is supported",
+                header.precision
+            )));
+        }
+        // Adobe applications write CMYK JPEGs with inverted components.
+        let decode = if header.components == 4 && header.adobe {
+            Some("1 0 1 0 1 0 1 0".to_string())
+        } else {
+            None
+        };
+        Ok(Image {
+            width: u32::from(header.width),
+            height: u32::from(header.height),
+            color_space: color_space.to_string(),
+            bits_per_component: header.precision,
+            decode,
+            filter: Some("DCTDecode"),
+            data: ImageData::new(data.to_vec()),
+            ..Image::default()
+        })
+    }
+
+    /// Create an image from the contents of a PNG file.
+    ///
+    /// All color types and bit depths are supported. Palette images use an
+    /// indexed color space, and the alpha channel or transparent palette
+    /// entries become a soft mask. A single transparent color of gray and
+    /// RGB images becomes a color key mask. Images with 16 bits per
+    /// component require PDF 1.5.
+    ///
+    /// Returns an error of kind `InvalidData` if `data` is not a valid PNG
+    /// file.
+    pub fn png(data: &[u8]) -> Result<Image> {
+        png::decode(data)
+    }
+
+    /// Create an image from uncompressed pixel data.
+    ///
+    /// Each row of pixels starts at a new byte, with the components of each
+    /// pixel next to each other. Components with 16 bits are stored with the
+    /// most significant byte first and require PDF 1.5.
+    ///
+    /// Returns an error of kind `InvalidInput` if `bits_per_component` is
+    /// not 1, 2, 4, 8 or 16, the width or height is 0, or `data` has the
+    /// wrong length.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::{Image, PixelFormat};
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// // A 2 × 2 checkerboard of black and white pixels.
+    /// let board =
+    ///     Image::from_pixels(2, 2, PixelFormat::Gray, 8, &[0, 255, 255, 0])?;
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn from_pixels(
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        bits_per_component: u8,
+        data: &[u8],
+    ) -> Result<Image> {
+        let (color_space, components) = match format {
+            PixelFormat::Gray => ("/DeviceGray", 1),
+            PixelFormat::RGB => ("/DeviceRGB", 3),
+            PixelFormat::CMYK => ("/DeviceCMYK", 4),
+        };
+        if ![1, 2, 4, 8, 16].contains(&bits_per_component) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid number of bits per component",
+            ));
+        }
+        check_length(width, height, components * bits_per_component, data)?;
+        Ok(Image {
+            width,
+            height,
+            color_space: color_space.to_string(),
+            bits_per_component,
+            data: ImageData::new(data.to_vec()),
+            ..Image::default()
+        })
+    }
+
+    /// Create a stencil mask from 1 bit pixel data. The mask is painted with
+    /// the current fill color where a pixel is 0 and leaves the page
+    /// unchanged where it is 1; [set_decode](#method.set_decode) with
+    /// `[1.0, 0.0]` reverses this.
+    ///
+    /// Each row of pixels starts at a new byte, with the first pixel in the
+    /// most significant bit.
+    ///
+    /// Returns an error of kind `InvalidInput` if the width or height is 0,
+    /// or `data` has the wrong length.
+    pub fn stencil_mask(width: u32, height: u32, data: &[u8]) -> Result<Image> {
+        check_length(width, height, 1, data)?;
+        Ok(Image {
+            width,
+            height,
+            bits_per_component: 1,
+            image_mask: true,
+            data: ImageData::new(data.to_vec()),
+            ..Image::default()
+        })
+    }
+
+    /// Set whether PDF viewers should smooth the image when it is scaled up.
+    pub fn set_interpolate(&mut self, interpolate: bool) {
+        self.interpolate = interpolate;
+    }
+
+    /// Set the decode array, which maps each component to a range of values
+    /// given by a minimum and a maximum, e.g. `[1.0, 0.0]` inverts a gray
+    /// image.
+    ///
+    /// # Panics
+    /// Panics if `decode` doesn't have two values for every component of
+    /// the color space.
+    pub fn set_decode(&mut self, decode: &[f32]) {
+        assert!(
+            decode.len() == 2 * self.components(),
+            "The decode array needs two values for each component."
+        );
+        let values: Vec<String> =
+            decode.iter().map(|v| v.to_string()).collect();
+        self.decode = Some(values.join(" "));
+    }
+
+    /// Compress the pixel data of an image created from uncompressed data
+    /// with [from_pixels](#method.from_pixels) or
+    /// [stencil_mask](#method.stencil_mask).
+    ///
+    /// Returns an error of kind `InvalidInput` if the image is already
+    /// compressed, or the filter is CCITT Group 4 and the image does not
+    /// have one component of 1 bit.
+    ///
+    /// # Example
+    /// ````
+    /// # use simple_pdf::{Image, ImageFilter};
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// let mut scan = Image::stencil_mask(1728, 2, &[0xFF; 432])?;
+    /// scan.set_filter(ImageFilter::Smallest)?;
+    /// # Ok(())
+    /// # }
+    /// ````
+    pub fn set_filter(&mut self, filter: ImageFilter) -> Result<()> {
+        if self.filter.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the image is already compressed",
+            ));
+        }
+        let layout = filter::Layout {
+            width: self.width as usize,
+            height: self.height as usize,
+            components: self.components(),
+            bits_per_component: usize::from(self.bits_per_component),
+        };
+        let encoded =
+            filter::encode(filter, &layout, &self.data)?.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "CCITT encoding requires one component of 1 bit",
+                )
+            })?;
+        self.filter = encoded.filter;
+        self.decode_parms = encoded.decode_parms;
+        self.data = ImageData::new(encoded.data);
+        Ok(())
+    }
+
+    /// Get the width of the image in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the image in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of bits per color component.
+    pub(crate) fn bits_per_component(&self) -> u8 {
+        self.bits_per_component
+    }
+
+    /// Write the image XObject to a pdf file and return its object id.
+    pub(crate) fn write_object(&self, pdf: &mut Pdf) -> Result<usize> {
+        if self.bits_per_component == 16 {
+            pdf.require_version(5);
+        }
+        let smask_id = match self.smask {
+            Some(ref smask) => {
+                pdf.require_version(4);
+                Some(pdf.image_object_id(smask)?)
+            }
+            None => None,
+        };
+        pdf.write_new_object(|object_id, pdf| {
+            writeln!(pdf.output, "<< /Type /XObject /Subtype /Image")?;
+            for entry in self.entries(false) {
+                writeln!(pdf.output, "   {}", entry)?;
+            }
+            if let Some(ref mask) = self.mask {
+                writeln!(pdf.output, "   /Mask [{}]", mask)?;
+            }
+            if let Some(smask_id) = smask_id {
+                writeln!(pdf.output, "   /SMask {} 0 R", smask_id)?;
+            }
+            writeln!(
+                pdf.output,
+                "   /Length {}\n\
+                 >>\n\
+                 stream",
+                self.data.len()
+            )?;
+            pdf.output.write_all(&self.data)?;
+            writeln!(pdf.output, "\nendstream")?;
+            Ok(object_id)
+        })
+    }
+
+    /// Write the image as inline image to a content stream.
+    ///
+    /// Returns an error of kind `InvalidInput` if the image has a mask, which
+    /// inline images can't have.
+    pub(crate) fn write_inline(&self, output: &mut dyn Write) -> Result<()> {
+        if self.mask.is_some() || self.smask.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "inline images can't have masks",
+            ));
+        }
+        writeln!(output, "BI")?;
+        for entry in self.entries(true) {
+            writeln!(output, "{}", entry)?;
+        }
+        write!(output, "ID ")?;
+        output.write_all(&self.data)?;
+        writeln!(output, "\nEI")
+    }
+
+    /// Return the entries of the image dictionary that inline images have
+    /// as well, with the abbreviated keys and names of inline images if
+    /// `inline` is set.
+    fn entries(&self, inline: bool) -> Vec<String> {
+        let key = |full, short| if inline { short } else { full };
+        let mut entries = vec![
+            format!("/{} {}", key("Width", "W"), self.width),
+            format!("/{} {}", key("Height", "H"), self.height),
+        ];
+        if self.image_mask {
+            entries.push(format!("/{} true", key("ImageMask", "IM")));
+        } else {
+            let mut color_space = self.color_space.clone();
+            if inline {
+                for &(full, short) in &[
+                    ("/DeviceGray", "/G"),
+                    ("/DeviceRGB", "/RGB"),
+                    ("/DeviceCMYK", "/CMYK"),
+                    ("/Indexed", "/I"),
+                ] {
+                    color_space = color_space.replace(full, short);
+                }
+            }
+            entries.push(format!(
+                "/{} {}",
+                key("ColorSpace", "CS"),
+                color_space
+            ));
+            entries.push(format!(
+                "/{} {}",
+                key("BitsPerComponent", "BPC"),
+                self.bits_per_component
+            ));
+        }
+        if let Some(ref decode) = self.decode {
+            entries.push(format!("/{} [{}]", key("Decode", "D"), decode));
+        }
+        if self.interpolate {
+            entries.push(format!("/{} true", key("Interpolate", "I")));
+        }
+        if let Some(filter) = self.filter {
+            let filter = if inline {
+                match filter {
+                    "FlateDecode" => "Fl",
+                    "DCTDecode" => "DCT",
+                    "RunLengthDecode" => "RL",
+                    "CCITTFaxDecode" => "CCF",
+                    _ => filter,
+                }
+            } else {
+                filter
+            };
+            entries.push(format!("/{} /{}", key("Filter", "F"), filter));
+        }
+        if let Some(ref decode_parms) = self.decode_parms {
+            entries.push(format!(
+                "/{} {}",
+                key("DecodeParms", "DP"),
+                decode_parms
+            ));
+        }
+        entries
+    }
+
+    /// The number of color components per pixel.
+    fn components(&self) -> usize {
+        match self.color_space.as_str() {
+            "/DeviceRGB" => 3,
+            "/DeviceCMYK" => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// The color space of the pixels of an image created with
+/// [Image::from_pixels](struct.Image.html#method.from_pixels).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PixelFormat {
+    /// One gray component per pixel, 0 is black.
+    Gray,
+    /// Red, green and blue components.
+    RGB,
+    /// Cyan, magenta, yellow and black components.
+    CMYK,
+}
+
+/// Check that an image is not empty and its pixel data has the expected
+/// length.
+fn check_length(
+    width: u32,
+    height: u32,
+    bits_per_pixel: u8,
+    data: &[u8],
+) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "image without size"));
+    }
+    let expected = (width as usize)
+        .checked_mul(usize::from(bits_per_pixel))
+        .map(|bits| bits.div_ceil(8))
+        .and_then(|row_bytes| row_bytes.checked_mul(height as usize));
+    if expected != Some(data.len()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "image of {} × {} pixels with {} bytes of pixel data",
+                width,
+                height,
+                data.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// The properties of a JPEG image found in its start of frame segment.
+#[derive(Debug, PartialEq)]
+struct JpegHeader {
+    width: u16,
+    height: u16,
+    components: u8,
+    precision: u8,
+    /// Whether the image has an Adobe application segment.
+    adobe: bool,
+}
+
+impl JpegHeader {
+    /// Read the segments of a JPEG file up to the start of frame.
+    fn parse(data: &[u8]) -> Result<JpegHeader> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return Err(invalid("not a JPEG image"));
+        }
+        let mut adobe = false;
+        let mut pos = 2;
+        loop {
+            // Markers may be preceded by any number of fill bytes.
+            while data.get(pos) == Some(&0xFF)
+                && data.get(pos + 1) == Some(&0xFF)
+            {
+                pos += 1;
+            }
+            let (marker, length) = match data.get(pos..pos + 4) {
+                Some(&[0xFF, marker, hi, lo]) => {
+                    (marker, usize::from(hi) << 8 | usize::from(lo))
+                }
+                _ => return Err(invalid("truncated JPEG image")),
+            };
+            let segment = data
+                .get(pos + 4..pos + 2 + length)
+                .ok_or_else(|| invalid("truncated JPEG image"))?;
+            match marker {
+                // Start of frame, except for DHT, JPG and DAC markers.
+                0xC0..=0xCF if ![0xC4, 0xC8, 0xCC].contains(&marker) => {
+                    if segment.len() < 6 {
+                        return Err(invalid("truncated JPEG image"));
+                    }
+                    let header = JpegHeader {
+                        precision: segment[0],
+                        height: u16::from(segment[1]) << 8
+                            | u16::from(segment[2]),
+                        width: u16::from(segment[3]) << 8
+                            | u16::from(segment[4]),
+                        components: segment[5],
+                        adobe,
+                    };
+                    if header.width == 0 || header.height == 0 {
+                        return Err(invalid("JPEG image without size"));
+                    }
+                    return Ok(header);
+                }
+                0xEE if segment.starts_with(b"Adobe") => adobe = true,
+                0xD9 | 0xDA => {
+                    return Err(invalid("JPEG image without frame header"))
+                }
+                _ => {}
+            }
+            pos += 2 + length;
+        }
+    }
+}
+
+#[cfg(test)]
+fn jpeg_header(components: u8, adobe: bool) -> Vec<u8> {
+    let mut data = vec![0xFF, 0xD8];
+    data.extend_from_slice(&[0xFF, 0xE0, 0, 7, b'J', b'F', b'I', b'F', 0]);
+    if adobe {
+        data.extend_from_slice(&[0xFF, 0xEE, 0, 14]);
+        data.extend_from_slice(b"Adobe\x00\x64\x00\x00\x00\x00\x02");
+    }
+    data.extend_from_slice(&[0xFF, 0xFF, 0xC2, 0, 8 + 3 * components]);
+    data.extend_from_slice(&[8, 0x01, 0x2C, 0x02, 0x58, components]);
+    for i in 0..components {
+        data.extend_from_slice(&[i + 1, 0x11, 0]);
+    }
+    data.extend_from_slice(&[0xFF, 0xD9]);
+    data
+}
+
+#[test]
+fn test_jpeg_header() {
+    assert_eq!(
+        JpegHeader {
+            width: 600,
+            height: 300,
+            components: 3,
+            precision: 8,
+            adobe: false,
+        },
+        JpegHeader::parse(&jpeg_header(3, false)).unwrap()
+    );
+    let cmyk = Image::jpeg(&jpeg_header(4, true)).unwrap();
+    assert_eq!((600, 300), (cmyk.width(), cmyk.height()));
+    assert_eq!("/DeviceCMYK", cmyk.color_space);
+    assert_eq!(Some("1 0 1 0 1 0 1 0".to_string()), cmyk.decode);
+    assert_eq!(None, Image::jpeg(&jpeg_header(3, true)).unwrap().decode);
+}
+
+#[test]
+fn test_invalid_jpeg() {
+    let mut truncated = jpeg_header(1, false);
+    truncated.truncate(14);
+    assert!(Image::jpeg(&truncated).is_err());
+    assert!(Image::jpeg(&jpeg_header(2, false)).is_err());
+    assert!(Image::jpeg(b"\x89PNG\r\n\x1a\n").is_err());
+}
+
+#[test]
+fn test_inline_image() {
+    let mut image =
+        Image::from_pixels(2, 1, PixelFormat::RGB, 8, &[255, 0, 0, 0, 0, 255])
+            .unwrap();
+    image.set_interpolate(true);
+    image.set_decode(&[1.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+    let mut output = Vec::new();
+    image.write_inline(&mut output).unwrap();
+    assert_eq!(
+        &b"BI\n/W 2\n/H 1\n/CS /RGB\n/BPC 8\n/D [1 0 0 1 0 1]\n/I true\n\
+           ID \xFF\x00\x00\x00\x00\xFF\nEI\n"[..],
+        &output[..]
+    );
+}
+
+#[test]
+fn test_pixel_data_length() {
+    assert!(Image::from_pixels(3, 2, PixelFormat::Gray, 4, &[0; 4]).is_ok());
+    assert!(Image::from_pixels(3, 2, PixelFormat::Gray, 4, &[0; 3]).is_err());
+    assert!(Image::from_pixels(1, 1, PixelFormat::CMYK, 3, &[0; 2]).is_err());
+    assert!(Image::stencil_mask(9, 2, &[0; 4]).is_ok());
+    assert!(Image::stencil_mask(9, 2, &[0; 2]).is_err());
+    assert!(Image::from_pixels(0, 0, PixelFormat::Gray, 8, &[]).is_err());
+    assert!(Image::stencil_mask(8, 0, &[]).is_err());
+}
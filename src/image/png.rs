@@ -0,0 +1,516 @@
+use super::{invalid, Image, ImageData};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use std::io::{Error, Read, Result, Write};
+use std::sync::Arc;
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The starting column and row and the distances between pixels of the
+/// seven passes of an Adam7 interlaced image.
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// The image header chunk of a PNG file.
+#[derive(Debug, PartialEq)]
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(chunk: &[u8]) -> Result<Header> {
+        if chunk.len() != 13 {
+            return Err(invalid("invalid PNG header"));
+        }
+        let header = Header {
+            width: be_u32(&chunk[0..4]) as usize,
+            height: be_u32(&chunk[4..8]) as usize,
+            bit_depth: chunk[8],
+            color_type: chunk[9],
+            interlaced: chunk[12] == 1,
+        };
+        let valid_depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => &[],
+        };
+        if header.width == 0
+            || header.height == 0
+            || !valid_depths.contains(&header.bit_depth)
+            || chunk[10] != 0
+            || chunk[11] != 0
+            || chunk[12] > 1
+        {
+            return Err(invalid("invalid PNG header"));
+        }
+        Ok(header)
+    }
+
+    /// The number of samples per pixel.
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * usize::from(self.bit_depth)
+    }
+
+    /// The number of bytes of a row of `width` pixels.
+    fn row_bytes(&self, width: usize) -> Result<usize> {
+        width
+            .checked_mul(self.bits_per_pixel())
+            .map(|bits| bits.div_ceil(8))
+            .ok_or_else(too_large)
+    }
+
+    fn has_alpha(&self) -> bool {
+        self.color_type == 4 || self.color_type == 6
+    }
+}
+
+fn too_large() -> Error {
+    invalid("PNG image too large")
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0]) << 24
+        | u32::from(bytes[1]) << 16
+        | u32::from(bytes[2]) << 8
+        | u32::from(bytes[3])
+}
+
+/// Create an image from the contents of a PNG file.
+///
+/// Images without alpha channel that are not interlaced keep their
+/// compressed data, which PDF viewers decode with the PNG predictors. All
+/// others are decoded and compressed again, with the alpha channel split
+/// into a soft mask.
+pub(crate) fn decode(data: &[u8]) -> Result<Image> {
+    if !data.starts_with(SIGNATURE) {
+        return Err(invalid("not a PNG image"));
+    }
+    let mut header = None;
+    let mut palette = None;
+    let mut transparency = None;
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    loop {
+        let length = data
+            .get(pos..pos + 4)
+            .map(be_u32)
+            .ok_or_else(|| invalid("truncated PNG image"))?
+            as usize;
+        let chunk = data
+            .get(pos + 4..pos + 12 + length)
+            .ok_or_else(|| invalid("truncated PNG image"))?;
+        let (kind, body) = (&chunk[..4], &chunk[4..4 + length]);
+        let mut crc = Crc::new();
+        crc.update(&chunk[..4 + length]);
+        if crc.sum() != be_u32(&chunk[4 + length..]) {
+            return Err(invalid("corrupt PNG chunk"));
+        }
+        match kind {
+            b"IHDR" => header = Some(Header::parse(body)?),
+            b"PLTE" => palette = Some(body),
+            b"tRNS" => transparency = Some(body),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + length;
+    }
+    let header = header.ok_or_else(|| invalid("PNG image without header"))?;
+
+    let color_space = match header.color_type {
+        0 | 4 => "/DeviceGray".to_string(),
+        2 | 6 => "/DeviceRGB".to_string(),
+        _ => {
+            let palette = palette
+                .filter(|p| !p.is_empty() && p.len() % 3 == 0)
+                .ok_or_else(|| invalid("PNG image without palette"))?;
+            let hex: String =
+                palette.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("[/Indexed /DeviceRGB {} <{}>]", palette.len() / 3 - 1, hex)
+        }
+    };
+    // Gray and RGB images can have a single transparent color, which is
+    // written as color key mask.
+    let mask = match (header.color_type, transparency) {
+        (0, Some(t)) | (2, Some(t)) if t.len() >= 2 * header.channels() => {
+            let ranges: Vec<String> = t
+                .chunks(2)
+                .take(header.channels())
+                .map(|v| {
+                    let v = u16::from(v[0]) << 8 | u16::from(v[1]);
+                    format!("{} {}", v, v)
+                })
+                .collect();
+            Some(ranges.join(" "))
+        }
+        _ => None,
+    };
+    let palette_alpha = transparency.filter(|_| header.color_type == 3);
+    let image = Image {
+        width: header.width as u32,
+        height: header.height as u32,
+        color_space,
+        bits_per_component: header.bit_depth,
+        mask,
+        ..Image::default()
+    };
+
+    if !header.interlaced && !header.has_alpha() && palette_alpha.is_none() {
+        return Ok(Image {
+            filter: Some("FlateDecode"),
+            decode_parms: Some(format!(
+                "<< /Predictor 15 /Colors {} /BitsPerComponent {} \
+                 /Columns {} >>",
+                header.channels(),
+                header.bit_depth,
+                header.width
+            )),
+            data: ImageData::new(compressed),
+            ..image
+        });
+    }
+
+    let mut filtered = Vec::new();
+    ZlibDecoder::new(&compressed[..]).read_to_end(&mut filtered)?;
+    let pixels = if header.interlaced {
+        deinterlace(&header, &filtered)?
+    } else {
+        let row_bytes = header.row_bytes(header.width)?;
+        unfilter(&header, &filtered, row_bytes, header.height)?.0
+    };
+    let (color, alpha) = if header.has_alpha() {
+        let (color, alpha) = split_alpha(&header, &pixels);
+        (color, Some((alpha, header.bit_depth)))
+    } else if let Some(palette_alpha) = palette_alpha {
+        let alpha = expand_palette_alpha(&header, &pixels, palette_alpha);
+        (pixels, Some((alpha, 8)))
+    } else {
+        (pixels, None)
+    };
+    let smask = match alpha {
+        Some((alpha, bits_per_component)) => Some(Arc::new(Image {
+            width: image.width,
+            height: image.height,
+            color_space: "/DeviceGray".to_string(),
+            bits_per_component,
+            filter: Some("FlateDecode"),
+            data: ImageData::new(deflate(&alpha)?),
+            ..Image::default()
+        })),
+        None => None,
+    };
+    Ok(Image {
+        filter: Some("FlateDecode"),
+        data: ImageData::new(deflate(&color)?),
+        smask,
+        ..image
+    })
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Reverse the filters of `rows` rows of `row_bytes` bytes at the start of
+/// `data`. Returns the pixel data and the number of bytes read.
+fn unfilter(
+    header: &Header,
+    data: &[u8],
+    row_bytes: usize,
+    rows: usize,
+) -> Result<(Vec<u8>, usize)> {
+    // Filters work on bytes, comparing with the corresponding byte of the
+    // previous pixel.
+    let bpp = header.bits_per_pixel().div_ceil(8).max(1);
+    // The size comes from the header, so check that the data is there
+    // before allocating for it.
+    let size = row_bytes
+        .checked_add(1)
+        .and_then(|line_bytes| line_bytes.checked_mul(rows))
+        .ok_or_else(too_large)?;
+    if data.len() < size {
+        return Err(invalid("truncated PNG image data"));
+    }
+    let mut pixels = vec![0; row_bytes * rows];
+    for y in 0..rows {
+        let line = data
+            .get(y * (row_bytes + 1)..(y + 1) * (row_bytes + 1))
+            .ok_or_else(|| invalid("truncated PNG image data"))?;
+        let (previous, current) = pixels.split_at_mut(y * row_bytes);
+        let previous = if y > 0 {
+            &previous[(y - 1) * row_bytes..]
+        } else {
+            &[][..]
+        };
+        let current = &mut current[..row_bytes];
+        for x in 0..row_bytes {
+            let a = if x >= bpp { current[x - bpp] } else { 0 };
+            let b = previous.get(x).cloned().unwrap_or(0);
+            let c = if x >= bpp {
+                previous.get(x - bpp).cloned().unwrap_or(0)
+            } else {
+                0
+            };
+            let predictor = match line[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(invalid("invalid PNG filter type")),
+            };
+            current[x] = line[x + 1].wrapping_add(predictor);
+        }
+    }
+    Ok((pixels, rows * (row_bytes + 1)))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Combine the seven passes of an Adam7 interlaced image.
+fn deinterlace(header: &Header, data: &[u8]) -> Result<Vec<u8>> {
+    let bits = header.bits_per_pixel();
+    let row_bytes = header.row_bytes(header.width)?;
+    // The passes together have at least as many bytes as the image, so
+    // check that the data is there before allocating for it.
+    let size = row_bytes.checked_mul(header.height).ok_or_else(too_large)?;
+    if data.len() < size {
+        return Err(invalid("truncated PNG image data"));
+    }
+    let mut pixels = vec![0; size];
+    let mut pos = 0;
+    for &(x0, y0, dx, dy) in &ADAM7 {
+        if header.width <= x0 || header.height <= y0 {
+            continue;
+        }
+        let width = (header.width - x0).div_ceil(dx);
+        let height = (header.height - y0).div_ceil(dy);
+        let pass_row_bytes = header.row_bytes(width)?;
+        let (pass, read) =
+            unfilter(header, &data[pos..], pass_row_bytes, height)?;
+        pos += read;
+        for j in 0..height {
+            let source = &pass[j * pass_row_bytes..];
+            let target = &mut pixels[(y0 + j * dy) * row_bytes..];
+            for i in 0..width {
+                let x = x0 + i * dx;
+                if bits >= 8 {
+                    let n = bits / 8;
+                    target[x * n..(x + 1) * n]
+                        .copy_from_slice(&source[i * n..(i + 1) * n]);
+                } else {
+                    let value = sample(source, i, bits);
+                    target[x * bits / 8] |= value << (8 - bits - x * bits % 8);
+                }
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+/// Return the `index`th sample of `bits` bits (less than 8) of a row.
+fn sample(row: &[u8], index: usize, bits: usize) -> u8 {
+    let shift = 8 - bits - index * bits % 8;
+    (row[index * bits / 8] >> shift) & ((1 << bits) - 1) as u8
+}
+
+/// Split pixels with an alpha channel into color and alpha data.
+fn split_alpha(header: &Header, pixels: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let sample_bytes = usize::from(header.bit_depth) / 8;
+    let color_bytes = (header.channels() - 1) * sample_bytes;
+    let pixel_bytes = color_bytes + sample_bytes;
+    let pixel_count = header.width * header.height;
+    let mut color = Vec::with_capacity(pixel_count * color_bytes);
+    let mut alpha = Vec::with_capacity(pixel_count * sample_bytes);
+    for pixel in pixels.chunks(pixel_bytes) {
+        color.extend_from_slice(&pixel[..color_bytes]);
+        alpha.extend_from_slice(&pixel[color_bytes..]);
+    }
+    (color, alpha)
+}
+
+/// Return 8 bit alpha data for a palette image from the alpha values of the
+/// palette entries. Entries without alpha value are opaque.
+fn expand_palette_alpha(
+    header: &Header,
+    pixels: &[u8],
+    palette_alpha: &[u8],
+) -> Vec<u8> {
+    let bits = usize::from(header.bit_depth);
+    let row_bytes = pixels.len() / header.height;
+    let mut alpha = Vec::with_capacity(header.width * header.height);
+    for row in pixels.chunks(row_bytes) {
+        for x in 0..header.width {
+            let index = if bits == 8 {
+                row[x]
+            } else {
+                sample(row, x, bits)
+            };
+            alpha.push(*palette_alpha.get(usize::from(index)).unwrap_or(&255));
+        }
+    }
+    alpha
+}
+
+#[cfg(test)]
+fn png_file(
+    header: [u8; 13],
+    chunks: &[(&[u8; 4], &[u8])],
+    filtered: &[u8],
+) -> Vec<u8> {
+    let mut file = SIGNATURE.to_vec();
+    let compressed = deflate(filtered).unwrap();
+    let mut all = vec![(b"IHDR", &header[..])];
+    all.extend_from_slice(chunks);
+    all.push((b"IDAT", &compressed));
+    all.push((b"IEND", &[]));
+    for (kind, body) in all {
+        let length = body.len() as u32;
+        file.extend_from_slice(&length.to_be_bytes());
+        let start = file.len();
+        file.extend_from_slice(&kind[..]);
+        file.extend_from_slice(body);
+        let mut crc = Crc::new();
+        crc.update(&file[start..]);
+        file.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    file
+}
+
+#[cfg(test)]
+fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut result).unwrap();
+    result
+}
+
+#[test]
+fn test_png_passthrough() {
+    let filtered = [0, 255, 0, 0, 0, 0, 255, 2, 0, 0, 0, 0, 0, 0];
+    let file = png_file(
+        [0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0],
+        &[(b"tRNS", &[0, 255, 0, 0, 0, 0])],
+        &filtered,
+    );
+    let image = decode(&file).unwrap();
+    assert_eq!("/DeviceRGB", image.color_space);
+    assert_eq!(Some("255 255 0 0 0 0".to_string()), image.mask);
+    assert_eq!(
+        Some(
+            "<< /Predictor 15 /Colors 3 /BitsPerComponent 8 /Columns 2 >>"
+                .to_string()
+        ),
+        image.decode_parms
+    );
+    assert_eq!(&filtered[..], &inflate(&image.data)[..]);
+}
+
+#[test]
+fn test_png_alpha() {
+    // Two gray and alpha pixels per row, filtered with Sub and Paeth.
+    let filtered = [1, 10, 255, 5, 0, 4, 20, 128, 5, 0];
+    let file =
+        png_file([0, 0, 0, 2, 0, 0, 0, 2, 8, 4, 0, 0, 0], &[], &filtered);
+    let image = decode(&file).unwrap();
+    assert_eq!(None, image.decode_parms);
+    assert_eq!(vec![10, 15, 30, 35], inflate(&image.data));
+    let smask = image.smask.unwrap();
+    assert_eq!("/DeviceGray", smask.color_space);
+    assert_eq!(vec![255, 255, 127, 127], inflate(&smask.data));
+}
+
+#[test]
+fn test_png_palette_alpha() {
+    // Three 2 bit palette indices: 2, 1, 0.
+    let file = png_file(
+        [0, 0, 0, 3, 0, 0, 0, 1, 2, 3, 0, 0, 0],
+        &[
+            (b"PLTE", &[0, 0, 0, 255, 0, 0, 0, 0, 255]),
+            (b"tRNS", &[0, 128]),
+        ],
+        &[0, 0b1001_0000],
+    );
+    let image = decode(&file).unwrap();
+    assert_eq!(
+        "[/Indexed /DeviceRGB 2 <000000FF00000000FF>]",
+        image.color_space
+    );
+    assert_eq!(2, image.bits_per_component);
+    assert_eq!(vec![0b1001_0000], inflate(&image.data));
+    assert_eq!(vec![255, 128, 0], inflate(&image.smask.unwrap().data));
+}
+
+#[test]
+fn test_png_interlaced() {
+    // A 3 × 3 gray image with pixel values 0 to 8, which has pixels in the
+    // passes 1, 4, 5, 6 and 7.
+    let filtered = [0, 0, 0, 2, 0, 6, 8, 0, 1, 0, 7, 0, 3, 4, 5];
+    let file =
+        png_file([0, 0, 0, 3, 0, 0, 0, 3, 8, 0, 0, 0, 1], &[], &filtered);
+    let image = decode(&file).unwrap();
+    assert_eq!(vec![0, 1, 2, 3, 4, 5, 6, 7, 8], inflate(&image.data));
+}
+
+#[test]
+fn test_invalid_png() {
+    let mut file =
+        png_file([0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0], &[], &[0, 0]);
+    assert!(decode(&file[..20]).is_err());
+    file[20] ^= 1;
+    assert!(decode(&file).is_err());
+    let file = png_file([0, 0, 0, 1, 0, 0, 0, 1, 4, 2, 0, 0, 0], &[], &[0, 0]);
+    assert!(decode(&file).is_err());
+}
+
+#[test]
+fn test_png_size_from_header() {
+    use std::io::ErrorKind;
+    // RGBA images with a single row of data, whose size overflows or
+    // would take gigabytes, plain and interlaced.
+    for &(size, interlaced) in &[(127, 0), (127, 1), (0, 0), (0, 1)] {
+        let ihdr = [
+            size, 255, 255, 255, size, 255, 255, 255, 16, 6, 0, 0, interlaced,
+        ];
+        let file = png_file(ihdr, &[], &[0; 9]);
+        let err = decode(&file).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+}
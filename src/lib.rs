@@ -53,10 +53,12 @@ extern crate lazy_static;
 
 extern crate time;
 
-use std::collections::{BTreeMap, HashMap};
+extern crate flate2;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fs::File;
-use std::io::{BufWriter, Result, Seek, SeekFrom, Write};
+use std::io::{self, BufWriter, Result, Seek, SeekFrom, Write};
 use std::mem;
 
 #[macro_use]
@@ -78,8 +80,18 @@ pub use encoding::{Encoding, FontEncoding};
 
 pub mod graphicsstate;
 
+pub mod geometry;
+use graphicsstate::{
+    ColorSpace, IccProfile, OutputIntent, Pattern, TilingPattern,
+};
+
 mod outline;
-use outline::OutlineItem;
+pub use outline::{OutlineId, OutlineItem};
+
+mod destination;
+pub use destination::Fit;
+
+mod annotation;
 
 mod canvas;
 pub use canvas::Canvas;
@@ -87,9 +99,35 @@ pub use canvas::Canvas;
 mod textobject;
 pub use textobject::{RenderMode, TextObject};
 
+mod xobject;
+pub use xobject::Form;
+
+mod image;
+pub use image::{Image, ImageFilter, PixelFormat};
+
+mod resources;
+use resources::{NamedRefs, ResourceRefs, Resources};
+
+mod serialize;
+use serialize::{write_literal_string, TextString};
+
+mod page;
+pub use page::PageSetup;
+
+mod pagelabel;
+pub use pagelabel::{PageLabel, PageLabelStyle};
+
+mod nametree;
+use nametree::TreeKind;
+
 const DEFAULT_BUF_SIZE: usize = 65_536;
 const ROOT_OBJECT_ID: usize = 1;
 const PAGE_OBJECT_ID: usize = 2;
+/// The lowest PDF version written, as minor version of PDF 1.x.
+const MIN_VERSION: u8 = 3;
+/// The position of the version number in the file header, relative to the
+/// start of the header.
+const VERSION_OFFSET: u64 = 5;
 
 // sorted manually alphabetical
 #[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Hash, Copy, Clone)]
@@ -131,9 +169,23 @@ pub struct Pdf {
     output: BufWriter<File>,
     object_offsets: Vec<i64>,
     page_object_ids: Vec<usize>,
+    rendered_pages: usize,
     font_object_ids: HashMap<Font, usize>,
-    outline: Vec<OutlineItem>,
+    color_space_object_ids: HashMap<ColorSpace, usize>,
+    dictionary_object_ids: HashMap<String, usize>,
+    stream_object_ids: HashMap<(String, Vec<u8>), usize>,
+    image_object_ids: HashMap<Image, usize>,
+    outline: Vec<(OutlineId, OutlineItem)>,
+    next_outline_id: usize,
+    named_destinations: BTreeMap<String, (usize, Fit)>,
+    /// The names of the destinations that links point to.
+    linked_destinations: BTreeSet<String>,
+    page_labels: BTreeMap<usize, PageLabel>,
+    output_intents: Vec<OutputIntent>,
     info: BTreeMap<MetaData, String>,
+    version: u8,
+    /// The position of the file header in the output.
+    header_position: u64,
 }
 
 impl Pdf {
@@ -145,17 +197,31 @@ impl Pdf {
 
     /// Create a new PDF document, writing to `output`.
     pub fn new(mut output: File) -> Result<Pdf> {
-        // TODO Maybe use a lower version?  Possibly decide by features used?
-        output.write_all(b"%PDF-1.7\n%\xB5\xED\xAE\xFB\n")?;
+        let header_position = output.stream_position()?;
+        // The version is raised by finish, depending on the features used.
+        writeln!(output, "%PDF-1.{}", MIN_VERSION)?;
+        output.write_all(b"%\xB5\xED\xAE\xFB\n")?;
         Ok(Pdf {
             output: BufWriter::with_capacity(DEFAULT_BUF_SIZE, output),
             // Object ID 0 is special in PDF.
             // We reserve IDs 1 and 2 for the catalog and page tree.
             object_offsets: vec![-1, -1, -1],
             page_object_ids: Vec::new(),
+            rendered_pages: 0,
             font_object_ids: HashMap::new(),
+            color_space_object_ids: HashMap::new(),
+            dictionary_object_ids: HashMap::new(),
+            stream_object_ids: HashMap::new(),
+            image_object_ids: HashMap::new(),
             outline: Vec::new(),
+            next_outline_id: 0,
+            named_destinations: BTreeMap::new(),
+            linked_destinations: BTreeSet::new(),
+            page_labels: BTreeMap::new(),
+            output_intents: Vec::new(),
             info: BTreeMap::new(),
+            version: MIN_VERSION,
+            header_position,
         })
     }
     /// Set metadata: the document's title.
@@ -186,6 +252,137 @@ impl Pdf {
         self.info.insert(MetaData::Producer, producer.to_string());
     }
 
+    /// Label the pages from index `first_page` (counting from 0) up to the
+    /// next page with a label set with `label`, e.g. to number the front
+    /// matter of a book with roman numerals.  Pages before the first label
+    /// are numbered with decimal numbers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use simple_pdf::{PageLabel, PageLabelStyle, Pdf};
+    /// # let mut document = Pdf::create("foo.pdf").unwrap();
+    /// // i, ii, iii, 1, 2, …, A-1, A-2, …
+    /// document.set_page_label(0, PageLabel::new(PageLabelStyle::LowerRoman));
+    /// document.set_page_label(3, PageLabel::new(PageLabelStyle::Decimal));
+    /// let mut appendix = PageLabel::new(PageLabelStyle::Decimal);
+    /// appendix.set_prefix("A-");
+    /// document.set_page_label(40, appendix);
+    /// ```
+    pub fn set_page_label(&mut self, first_page: usize, label: PageLabel) {
+        self.page_labels.insert(first_page, label);
+    }
+
+    /// Embed an ICC color profile in the document. The profile can be used
+    /// for [colors](graphicsstate/struct.Color.html#method.icc) and
+    /// [output intents](graphicsstate/struct.OutputIntent.html).
+    ///
+    /// Returns an error of kind `InvalidData` if `data` is not a gray, RGB or
+    /// CMYK ICC profile.
+    pub fn add_icc_profile(&mut self, data: &[u8]) -> Result<IccProfile> {
+        IccProfile::write_object(self, data)
+    }
+    /// Add an output intent, describing the color characteristics of the
+    /// device the document is intended for.
+    pub fn add_output_intent(&mut self, intent: OutputIntent) {
+        self.require_version(4);
+        self.output_intents.push(intent);
+    }
+
+    /// Create a tiling pattern whose cell is drawn by the function
+    /// `render_cell`. The pattern can be used as color on any page of the
+    /// document.
+    pub fn create_tiling_pattern<F>(
+        &mut self,
+        pattern: &TilingPattern,
+        render_cell: F,
+    ) -> Result<Pattern>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+    {
+        pattern.write_object(self, render_cell)
+    }
+
+    /// Create a form XObject, i.e. content that is drawn once by the function
+    /// `render_contents` and can be placed on any page of the document with
+    /// [Canvas::draw_form](struct.Canvas.html#method.draw_form).
+    ///
+    /// The content is clipped to the bounding box with a corner at (x, y)
+    /// and extending width × height.
+    ///
+    /// # Example
+    /// ````
+    /// # #[macro_use]
+    /// # extern crate simple_pdf;
+    /// # use simple_pdf::units::{Points, UserSpace};
+    /// # use simple_pdf::graphicsstate::Matrix;
+    /// # use simple_pdf::{BuiltinFont, FontSource, Pdf};
+    /// # use std::io;
+    /// # fn main() -> io::Result<()> {
+    /// # let mut document = Pdf::create("foo.pdf")?;
+    /// let letterhead =
+    ///     document.create_form(pt!(0), pt!(0), pt!(200), pt!(20), |canvas| {
+    ///         canvas.center_text(pt!(100), pt!(5), &BuiltinFont::Times_Bold,
+    ///                            pt!(14), "ACME Corporation")
+    ///     })?;
+    /// for _ in 0..3 {
+    ///     document.render_page(pt!(200), pt!(300), |canvas| {
+    ///         canvas.draw_form(letterhead, Matrix::translate(pt!(0), pt!(280)))
+    ///     })?;
+    /// }
+    /// # document.finish()
+    /// # }
+    /// ````
+    pub fn create_form<F, T>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+        render_contents: F,
+    ) -> Result<Form>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+        T: LengthUnit,
+    {
+        let (content, resources) = self.render_content(render_contents)?;
+        let bbox = [x.pt, y.pt, (x + width).pt, (y + height).pt];
+        let object_id = self.write_form(bbox, None, &content, resources)?;
+        Ok(Form::new(object_id))
+    }
+
+    /// Render content that is not part of a page, like a form or a pattern
+    /// cell, and return it with its resources.
+    fn render_content<F>(
+        &mut self,
+        render_contents: F,
+    ) -> Result<(Vec<u8>, Resources)>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+    {
+        let mut content = Vec::new();
+        let mut resources = Resources::default();
+        // Outline items, links and destinations need a page and are
+        // dropped.
+        let mut outline = Vec::new();
+        let mut links = Vec::new();
+        let mut destinations = Vec::new();
+        render_contents(&mut Canvas::new(
+            self,
+            &mut content,
+            &mut resources,
+            &mut outline,
+            &mut links,
+            &mut destinations,
+        ))?;
+        Ok((content, resources))
+    }
+
+    /// Raise the version in the file header to at least PDF 1.`minor`.
+    fn require_version(&mut self, minor: u8) {
+        self.version = self.version.max(minor);
+    }
+
     /// Return the current read/write position in the output file.
     fn tell(&mut self) -> Result<u64> {
         self.output.seek(SeekFrom::Current(0))
@@ -206,80 +403,269 @@ impl Pdf {
         F: FnOnce(&mut Canvas) -> Result<()>,
         T: LengthUnit,
     {
-        let (content_object_id, content_length, fonts, outline) = self
-            .write_new_object(move |content_object_id, pdf| {
-                // Guess the ID of the next object. (We’ll assert it below.)
-                writeln!(
-                    pdf.output,
-                    "<< /Length {} 0 R >>\n\
-                     stream",
-                    content_object_id + 1
-                )?;
-
-                let start = pdf.tell()?;
-                writeln!(pdf.output, "/DeviceRGB cs /DeviceRGB CS")?;
-                let mut fonts = HashMap::new();
-                let mut outline = Vec::new();
-                render_contents(&mut Canvas::new(
-                    &mut pdf.output,
-                    &mut fonts,
-                    &mut outline,
-                ))?;
-                let end = pdf.tell()?;
-
-                writeln!(pdf.output, "endstream")?;
-                Ok((content_object_id, end - start, fonts, outline))
-            })?;
-
-        self.write_new_object(|object_id_length, pdf| {
-            assert!(object_id_length == content_object_id + 1);
-            writeln!(pdf.output, "{}", content_length)
+        self.render_page_with(&PageSetup::new(width, height), render_contents)
+    }
+
+    /// Create a new page in the PDF document, with the boxes, rotation and
+    /// user unit of `setup`.
+    ///
+    /// Returns an error of kind `InvalidInput` if the boxes of `setup` are
+    /// empty or do not nest properly.
+    pub fn render_page_with<F>(
+        &mut self,
+        setup: &PageSetup,
+        render_contents: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut Canvas) -> Result<()>,
+    {
+        setup.validate()?;
+        if setup.user_unit().is_some() {
+            self.require_version(6);
+        }
+        // The content is collected in memory, so the canvas can write other
+        // objects like transparency groups while the page is rendered.
+        let mut content = Vec::new();
+        let mut resources = Resources::default();
+        let mut outline = Vec::new();
+        let mut links = Vec::new();
+        let mut destinations = Vec::new();
+        render_contents(&mut Canvas::new(
+            self,
+            &mut content,
+            &mut resources,
+            &mut outline,
+            &mut links,
+            &mut destinations,
+        ))?;
+        let content_object_id = self.write_new_object(|object_id, pdf| {
+            writeln!(
+                pdf.output,
+                "<< /Length {} >>\n\
+                 stream",
+                content.len()
+            )?;
+            pdf.output.write_all(&content)?;
+            writeln!(pdf.output, "endstream")?;
+            Ok(object_id)
         })?;
 
-        let mut font_oids = NamedRefs::with_capacity(fonts.len());
-        for (source, fontref) in fonts {
-            if let Some(&object_id) = self.font_object_ids.get(&source) {
-                font_oids.insert(fontref, object_id);
-            } else {
-                let object_id = source.write_object(self)?;
-                font_oids.insert(fontref, object_id);
-                self.font_object_ids.insert(source, object_id);
+        let resource_oids = self.write_resources(resources)?;
+        let mut annotation_oids = Vec::with_capacity(links.len());
+        for link in links {
+            if let Some(name) = link.destination_name() {
+                self.linked_destinations.insert(name.to_string());
             }
+            let page_id = link.page_index().map(|i| self.page_object_id(i));
+            annotation_oids.push(self.write_new_object(|object_id, pdf| {
+                link.write_dictionary(&mut pdf.output, page_id)?;
+                Ok(object_id)
+            })?);
         }
-        let page_oid =
-            self.write_page_dict(content_object_id, width, height, &font_oids)?;
+        let page_oid = self.page_object_id(self.rendered_pages);
+        self.write_page_dict(
+            page_oid,
+            content_object_id,
+            setup,
+            &resource_oids,
+            &annotation_oids,
+        )?;
         // Take the outline from this page, mark them with the page ref,
         // and save them for the document outline.
-        for mut item in outline {
+        for (id, mut item) in outline {
             item.set_page(page_oid);
-            self.outline.push(item);
+            self.outline.push((id, item));
+        }
+        for (name, fit) in destinations {
+            self.named_destinations.insert(name, (page_oid, fit));
         }
-        self.page_object_ids.push(page_oid);
+        self.rendered_pages += 1;
         Ok(())
     }
 
-    fn write_page_dict<T: LengthUnit>(
+    /// Return the object id of the page with the given index, reserving ids
+    /// for pages that are not rendered yet so they can be referenced.
+    fn page_object_id(&mut self, index: usize) -> usize {
+        while self.page_object_ids.len() <= index {
+            self.page_object_ids.push(self.object_offsets.len());
+            self.object_offsets.push(-1);
+        }
+        self.page_object_ids[index]
+    }
+
+    /// Write the objects of all resources used in a content stream that are
+    /// not yet part of the document, and return the object ids by name.
+    fn write_resources(
         &mut self,
-        content_oid: usize,
-        width: UserSpace<T>,
-        height: UserSpace<T>,
-        font_oids: &NamedRefs,
+        resources: Resources,
+    ) -> Result<ResourceRefs> {
+        let mut font_oids = NamedRefs::with_capacity(resources.fonts.len());
+        for (source, fontref) in resources.fonts {
+            if let Some(&object_id) = self.font_object_ids.get(&source) {
+                font_oids.insert(fontref.to_string(), object_id);
+            } else {
+                let object_id = source.write_object(self)?;
+                font_oids.insert(fontref.to_string(), object_id);
+                self.font_object_ids.insert(source, object_id);
+            }
+        }
+        let mut color_space_oids =
+            NamedRefs::with_capacity(resources.color_spaces.len());
+        for (color_space, n) in resources.color_spaces {
+            let object_id = self.color_space_object_id(color_space)?;
+            color_space_oids.insert(format!("/CS{}", n), object_id);
+        }
+        let mut ext_gstate_oids =
+            NamedRefs::with_capacity(resources.ext_gstates.len());
+        for (ext_gstate, n) in resources.ext_gstates {
+            // Graphics state parameters are only used for transparency.
+            self.require_version(4);
+            let object_id = self.dictionary_object_id(ext_gstate)?;
+            ext_gstate_oids.insert(format!("/GS{}", n), object_id);
+        }
+        let named_objects = |objects: HashMap<usize, usize>, prefix: &str| {
+            let mut oids = NamedRefs::with_capacity(objects.len());
+            for (object_id, n) in objects {
+                oids.insert(format!("/{}{}", prefix, n), object_id);
+            }
+            oids
+        };
+        Ok(ResourceRefs {
+            fonts: font_oids,
+            color_spaces: color_space_oids,
+            ext_gstates: ext_gstate_oids,
+            patterns: named_objects(resources.patterns, "P"),
+            shadings: named_objects(resources.shadings, "Sh"),
+            xobjects: named_objects(resources.xobjects, "X"),
+        })
+    }
+
+    /// Return the object id of a color space, writing it if it is not yet
+    /// part of the document.
+    fn color_space_object_id(
+        &mut self,
+        color_space: ColorSpace,
+    ) -> Result<usize> {
+        if let Some(&object_id) = self.color_space_object_ids.get(&color_space)
+        {
+            return Ok(object_id);
+        }
+        let object_id = color_space.write_object(self)?;
+        self.color_space_object_ids.insert(color_space, object_id);
+        Ok(object_id)
+    }
+
+    /// Return the object id of a dictionary, writing it if it is not yet part
+    /// of the document. Equal dictionaries share one object.
+    fn dictionary_object_id(&mut self, dictionary: String) -> Result<usize> {
+        if let Some(&object_id) = self.dictionary_object_ids.get(&dictionary) {
+            return Ok(object_id);
+        }
+        let object_id = self.write_new_object(|object_id, pdf| {
+            writeln!(pdf.output, "{}", dictionary)?;
+            Ok(object_id)
+        })?;
+        self.dictionary_object_ids.insert(dictionary, object_id);
+        Ok(object_id)
+    }
+
+    /// Return the object id of an image, writing it if it is not yet part of
+    /// the document.
+    fn image_object_id(&mut self, image: &Image) -> Result<usize> {
+        if let Some(&object_id) = self.image_object_ids.get(image) {
+            return Ok(object_id);
+        }
+        let object_id = image.write_object(self)?;
+        self.image_object_ids.insert(image.clone(), object_id);
+        Ok(object_id)
+    }
+
+    /// Return the object id of a stream with the given dictionary and data,
+    /// writing it if it is not yet part of the document.
+    fn stream_object_id(
+        &mut self,
+        dictionary: String,
+        data: Vec<u8>,
+    ) -> Result<usize> {
+        let key = (dictionary, data);
+        if let Some(&object_id) = self.stream_object_ids.get(&key) {
+            return Ok(object_id);
+        }
+        let object_id = self.write_new_object(|object_id, pdf| {
+            writeln!(pdf.output, "{}\nstream", key.0)?;
+            pdf.output.write_all(&key.1)?;
+            writeln!(pdf.output, "\nendstream")?;
+            Ok(object_id)
+        })?;
+        self.stream_object_ids.insert(key, object_id);
+        Ok(object_id)
+    }
+
+    /// Write a form XObject with the given bounding box, content and
+    /// resources, and return its object id. The optional group attributes
+    /// make the form a transparency group.
+    fn write_form(
+        &mut self,
+        bbox: [f32; 4],
+        group: Option<&str>,
+        content: &[u8],
+        resources: Resources,
     ) -> Result<usize> {
-        self.write_new_object(|page_oid, pdf| {
+        let resource_oids = self.write_resources(resources)?;
+        if group.is_some() {
+            self.require_version(4);
+        }
+        self.write_new_object(|object_id, pdf| {
+            write!(
+                pdf.output,
+                "<< /Type /XObject /Subtype /Form\n   \
+                 /BBox [{} {} {} {}]\n   \
+                 /Resources {}\n   ",
+                bbox[0], bbox[1], bbox[2], bbox[3], resource_oids
+            )?;
+            if let Some(group) = group {
+                write!(pdf.output, "/Group {}\n   ", group)?;
+            }
+            writeln!(
+                pdf.output,
+                "/Length {}\n\
+                 >>\n\
+                 stream",
+                content.len()
+            )?;
+            pdf.output.write_all(content)?;
+            writeln!(pdf.output, "endstream")?;
+            Ok(object_id)
+        })
+    }
+
+    fn write_page_dict(
+        &mut self,
+        page_oid: usize,
+        content_oid: usize,
+        setup: &PageSetup,
+        resource_oids: &ResourceRefs,
+        annotation_oids: &[usize],
+    ) -> Result<()> {
+        self.write_object_with_id(page_oid, |pdf| {
             writeln!(
                 pdf.output,
                 "<< /Type /Page\n   \
                  /Parent {parent} 0 R\n   \
-                 /Resources << /Font << {fonts}>> >>\n   \
-                 /MediaBox [0 0 {width} {height}]\n   \
-                 /Contents {content} 0 R\n\
-                 >>",
+                 /Resources {resources}",
                 parent = PAGE_OBJECT_ID,
-                fonts = font_oids,
-                width = width,
-                height = height,
-                content = content_oid
-            ).map(|_| page_oid)
+                resources = resource_oids,
+            )?;
+            setup.write_entries(&mut pdf.output)?;
+            writeln!(pdf.output, "   /Contents {} 0 R", content_oid)?;
+            if !annotation_oids.is_empty() {
+                write!(pdf.output, "   /Annots [ ")?;
+                for id in annotation_oids {
+                    write!(pdf.output, "{} 0 R ", id)?;
+                }
+                writeln!(pdf.output, "]")?;
+            }
+            writeln!(pdf.output, ">>")
         })
     }
 
@@ -327,7 +713,37 @@ impl Pdf {
     /// Write out the document trailer. The trailer consists of the pages
     /// object, the root object, the xref list, the trailer object and the
     /// startxref position.
+    ///
+    /// Returns an error of kind `InvalidInput` if a link points to a page
+    /// that was not rendered, or a link or outline item points to a named
+    /// destination that was not added.
     pub fn finish(mut self) -> Result<()> {
+        if self.page_object_ids.len() > self.rendered_pages {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "link to page {} of a document with {} pages",
+                    self.page_object_ids.len() - 1,
+                    self.rendered_pages
+                ),
+            ));
+        }
+        let missing = self
+            .linked_destinations
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.outline
+                    .iter()
+                    .filter_map(|(_, item)| item.destination_name()),
+            )
+            .find(|name| !self.named_destinations.contains_key(*name));
+        if let Some(name) = missing {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("link to the missing destination {:?}", name),
+            ));
+        }
         self.write_object_with_id(PAGE_OBJECT_ID, |pdf| {
             write!(
                 pdf.output,
@@ -347,7 +763,7 @@ impl Pdf {
             self.write_new_object(|page_object_id, pdf| {
                 write!(pdf.output, "<<")?;
                 for (meta, value) in info {
-                    writeln!(pdf.output, " /{} ({})", meta, value)?;
+                    writeln!(pdf.output, " /{} {}", meta, TextString(&value))?;
                 }
                 if let Ok(now) = time::strftime("%Y%m%d%H%M%S%z", &time::now())
                 {
@@ -368,6 +784,8 @@ impl Pdf {
         };
 
         let outlines_id = self.write_outline()?;
+        let dests_id = self.write_named_destinations()?;
+        let page_labels_id = self.write_page_labels()?;
 
         self.write_object_with_id(ROOT_OBJECT_ID, |pdf| {
             writeln!(
@@ -379,6 +797,19 @@ impl Pdf {
             if let Some(outlines_id) = outlines_id {
                 writeln!(pdf.output, "/Outlines {} 0 R", outlines_id)?;
             }
+            if let Some(dests_id) = dests_id {
+                writeln!(pdf.output, "/Names << /Dests {} 0 R >>", dests_id)?;
+            }
+            if let Some(id) = page_labels_id {
+                writeln!(pdf.output, "/PageLabels {} 0 R", id)?;
+            }
+            if !pdf.output_intents.is_empty() {
+                writeln!(pdf.output, "/OutputIntents [")?;
+                for intent in &pdf.output_intents {
+                    intent.write_dictionary(&mut pdf.output)?;
+                }
+                writeln!(pdf.output, "]")?;
+            }
             writeln!(pdf.output, ">>")
         })?;
         let startxref = self.tell()?;
@@ -412,7 +843,45 @@ impl Pdf {
              {}\n\
              %%EOF",
             startxref
-        )
+        )?;
+        // Now that all used features are known, patch the file header.
+        let version_position = self.header_position + VERSION_OFFSET;
+        self.output.seek(SeekFrom::Start(version_position))?;
+        write!(self.output, "1.{}", self.version)?;
+        self.output.flush()
+    }
+
+    fn write_named_destinations(&mut self) -> Result<Option<usize>> {
+        if self.named_destinations.is_empty() {
+            return Ok(None);
+        }
+        let destinations = mem::take(&mut self.named_destinations);
+        let mut entries = Vec::with_capacity(destinations.len());
+        for (name, (page_id, fit)) in destinations {
+            let mut key = Vec::new();
+            write_literal_string(&mut key, name.as_bytes())?;
+            let value = format!("[{} 0 R {}]", page_id, fit).into_bytes();
+            entries.push((key, value));
+        }
+        nametree::write_tree(self, TreeKind::Names, &entries).map(Some)
+    }
+
+    fn write_page_labels(&mut self) -> Result<Option<usize>> {
+        if self.page_labels.is_empty() {
+            return Ok(None);
+        }
+        let mut labels = mem::take(&mut self.page_labels);
+        // The first page needs a label, as ranges only end at the next one.
+        labels
+            .entry(0)
+            .or_insert_with(|| PageLabel::new(PageLabelStyle::Decimal));
+        let mut entries = Vec::with_capacity(labels.len());
+        for (first_page, label) in labels {
+            let mut value = Vec::new();
+            label.write_dictionary(&mut value)?;
+            entries.push((first_page.to_string().into_bytes(), value));
+        }
+        nametree::write_tree(self, TreeKind::Nums, &entries).map(Some)
     }
 
     fn write_outline(&mut self) -> Result<Option<usize>> {
@@ -420,31 +889,54 @@ impl Pdf {
             return Ok(None);
         }
 
-        let parent_id = self.object_offsets.len();
-        self.object_offsets.push(-1);
-        let count = self.outline.len();
-        let mut first_id = 0;
-        let mut last_id = 0;
         let outline = mem::replace(&mut self.outline, Vec::new());
+        if outline.iter().any(|(_, item)| item.is_styled()) {
+            self.require_version(4);
+        }
+        let children = outline::children(&outline);
+        let counts = outline::visible_counts(&outline, &children);
+        // Reserve ids for the root and all items, so they can refer to each
+        // other.
+        let parent_id = self.object_offsets.len();
+        let item_id = |i: usize| parent_id + 1 + i;
+        let first_last =
+            |i: usize| match (children[i].first(), children[i].last()) {
+                (Some(&first), Some(&last)) => {
+                    Some((item_id(first), item_id(last)))
+                }
+                _ => None,
+            };
+        for _ in 0..=outline.len() {
+            self.object_offsets.push(-1);
+        }
 
-        for (i, item) in outline.iter().enumerate() {
-            let (is_first, is_last) = (i == 0, i == count - 1);
-            let id = self.write_new_object(|object_id, pdf| {
-                item.write_dictionary(
-                    &mut pdf.output,
-                    parent_id,
-                    if is_first { None } else { Some(object_id - 1) },
-                    if is_last { None } else { Some(object_id + 1) },
-                ).and(Ok(object_id))
-            })?;
-            if is_first {
-                first_id = id;
-            }
-            if is_last {
-                last_id = id;
+        for (p, siblings) in children.iter().enumerate() {
+            let parent = if p == outline.len() {
+                parent_id
+            } else {
+                item_id(p)
+            };
+            for (j, &i) in siblings.iter().enumerate() {
+                let item = &outline[i].1;
+                let count = if item.is_open() {
+                    counts[i]
+                } else {
+                    -counts[i]
+                };
+                self.write_object_with_id(item_id(i), |pdf| {
+                    item.write_dictionary(
+                        &mut pdf.output,
+                        parent,
+                        j.checked_sub(1).map(|k| item_id(siblings[k])),
+                        siblings.get(j + 1).map(|&k| item_id(k)),
+                        first_last(i),
+                        count,
+                    )
+                })?;
             }
         }
 
+        let (first_id, last_id) = first_last(outline.len()).unwrap();
         self.write_object_with_id(parent_id, |pdf| {
             writeln!(
                 pdf.output,
@@ -455,34 +947,163 @@ impl Pdf {
                  >>",
                 last = last_id,
                 first = first_id,
-                count = count
+                count = counts[outline.len()]
             )
         })?;
         Ok(Some(parent_id))
     }
 }
 
-struct NamedRefs {
-    oids: HashMap<FontRef, usize>,
+#[test]
+fn test_version_in_header_after_existing_data() {
+    use std::fs;
+    use std::io::Read;
+    let path = std::env::temp_dir().join("simple_pdf_header_position.pdf");
+    let mut file = File::create(&path).unwrap();
+    file.write_all(b"prefix\n").unwrap();
+    let mut document = Pdf::new(file).unwrap();
+    document.require_version(5);
+    document.finish().unwrap();
+    let mut written = Vec::new();
+    File::open(&path)
+        .unwrap()
+        .read_to_end(&mut written)
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(written.starts_with(b"prefix\n%PDF-1.5\n"));
 }
 
-impl NamedRefs {
-    fn with_capacity(capacity: usize) -> Self {
-        NamedRefs {
-            oids: HashMap::with_capacity(capacity),
-        }
-    }
+#[test]
+fn test_version_of_16_bit_inline_image() {
+    use std::fs;
+    use units::Points;
+    let path = std::env::temp_dir().join("simple_pdf_inline_image.pdf");
+    let mut document = Pdf::create(path.to_str().unwrap()).unwrap();
+    let image =
+        Image::from_pixels(1, 1, PixelFormat::Gray, 16, &[0, 0]).unwrap();
+    document
+        .render_page(pt!(10), pt!(10), |canvas| {
+            canvas.draw_inline_image(&image, pt!(0), pt!(0), pt!(10), pt!(10))
+        })
+        .unwrap();
+    document.finish().unwrap();
+    let written = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(written.starts_with(b"%PDF-1.5\n"));
+}
 
-    fn insert(&mut self, name: FontRef, object_id: usize) -> Option<usize> {
-        self.oids.insert(name, object_id)
-    }
+#[test]
+fn test_image_written_once() {
+    use std::fs;
+    use units::Points;
+    let path = std::env::temp_dir().join("simple_pdf_image_once.pdf");
+    let mut document = Pdf::create(path.to_str().unwrap()).unwrap();
+    let pixels = [0, 255, 255, 0];
+    let image =
+        Image::from_pixels(2, 2, PixelFormat::Gray, 8, &pixels).unwrap();
+    let same = Image::from_pixels(2, 2, PixelFormat::Gray, 8, &pixels).unwrap();
+    let mut smooth = image.clone();
+    smooth.set_interpolate(true);
+    document
+        .render_page(pt!(10), pt!(10), |canvas| {
+            for image in &[&image, &image.clone(), &same, &smooth] {
+                canvas.draw_image(image, pt!(0), pt!(0), pt!(10), pt!(10))?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    document.finish().unwrap();
+    let written = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let images = written
+        .windows(b"/Subtype /Image".len())
+        .filter(|w| w == b"/Subtype /Image")
+        .count();
+    assert_eq!(2, images);
 }
 
-impl fmt::Display for NamedRefs {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (name, id) in &self.oids {
-            write!(f, "{} {} 0 R ", name, id)?;
-        }
-        Ok(())
+#[test]
+fn test_nan_alpha() {
+    use std::fs;
+    use units::Points;
+    let path = std::env::temp_dir().join("simple_pdf_nan_alpha.pdf");
+    let mut document = Pdf::create(path.to_str().unwrap()).unwrap();
+    document
+        .render_page(pt!(10), pt!(10), |canvas| {
+            let fill = canvas.set_fill_alpha(std::f32::NAN);
+            let stroke = canvas.set_stroke_alpha(std::f32::NAN);
+            assert_eq!(io::ErrorKind::InvalidInput, fill.unwrap_err().kind());
+            assert_eq!(io::ErrorKind::InvalidInput, stroke.unwrap_err().kind());
+            canvas.set_fill_alpha(0.5)
+        })
+        .unwrap();
+    document.finish().unwrap();
+    let written = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let written = String::from_utf8_lossy(&written);
+    assert!(written.contains("<< /Type /ExtGState /ca 0.5 >>"));
+    assert!(!written.contains("NaN"));
+}
+
+#[test]
+fn test_version_of_styled_outline() {
+    use std::fs;
+    use units::Points;
+    let path = std::env::temp_dir().join("simple_pdf_styled_outline.pdf");
+    let mut document = Pdf::create(path.to_str().unwrap()).unwrap();
+    document
+        .render_page(pt!(10), pt!(10), |canvas| {
+            let mut item = OutlineItem::new("Chapter");
+            item.set_bold(true);
+            canvas.add_outline_item(item);
+            Ok(())
+        })
+        .unwrap();
+    document.finish().unwrap();
+    let written = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(written.starts_with(b"%PDF-1.4\n"));
+}
+
+#[test]
+fn test_link_to_missing_destination() {
+    use std::fs;
+    use units::Points;
+    let path = std::env::temp_dir().join("simple_pdf_missing_destination.pdf");
+    let render = |link: bool| {
+        let mut document = Pdf::create(path.to_str().unwrap()).unwrap();
+        document
+            .render_page(pt!(10), pt!(10), |canvas| {
+                canvas.add_named_destination("intro", pt!(0), pt!(10));
+                if link {
+                    canvas.link_to_destination(
+                        pt!(0),
+                        pt!(0),
+                        pt!(5),
+                        pt!(5),
+                        "outro",
+                    );
+                } else {
+                    let mut item = OutlineItem::new("Outro");
+                    item.set_named_destination("outro");
+                    canvas.add_outline_item(item);
+                }
+                canvas.link_to_destination(
+                    pt!(5),
+                    pt!(5),
+                    pt!(5),
+                    pt!(5),
+                    "intro",
+                );
+                Ok(())
+            })
+            .unwrap();
+        document.finish()
+    };
+    for &link in &[true, false] {
+        let err = render(link).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert!(err.to_string().contains("\"outro\""));
     }
+    fs::remove_file(&path).unwrap();
 }
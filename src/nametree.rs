@@ -0,0 +1,129 @@
+//! Name trees and number trees, as described in sections 7.9.6 and 7.9.7 of
+//! the PDF specification.
+use super::Pdf;
+use std::io::{Result, Write};
+
+/// The maximum number of entries or kids in a node of a tree.
+const NODE_SIZE: usize = 32;
+
+/// The kind of keys of a tree.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum TreeKind {
+    /// Keys are strings.
+    Names,
+    /// Keys are integers.
+    Nums,
+}
+
+/// A serialized key and value of a tree.
+pub(crate) type Entry = (Vec<u8>, Vec<u8>);
+
+/// Write a balanced tree with the given `entries`, that must be sorted by
+/// key, and return the object id of its root.
+pub(crate) fn write_tree(
+    pdf: &mut Pdf,
+    kind: TreeKind,
+    entries: &[Entry],
+) -> Result<usize> {
+    let key = match kind {
+        TreeKind::Names => "/Names",
+        TreeKind::Nums => "/Nums",
+    };
+    if entries.len() <= NODE_SIZE {
+        return write_node(pdf, key, entries, None);
+    }
+
+    // Each node is the object id with the first and last key below it.
+    let mut nodes = Vec::new();
+    let mut start = 0;
+    for size in chunk_sizes(entries.len()) {
+        let leaf = &entries[start..start + size];
+        let limits = (&leaf[0].0, &leaf[size - 1].0);
+        let id = write_node(pdf, key, leaf, Some(limits))?;
+        nodes.push((id, limits.0.clone(), limits.1.clone()));
+        start += size;
+    }
+    while nodes.len() > NODE_SIZE {
+        let mut parents = Vec::new();
+        let mut start = 0;
+        for size in chunk_sizes(nodes.len()) {
+            let kids = &nodes[start..start + size];
+            let limits = (&kids[0].1, &kids[size - 1].2);
+            let id = write_kids(pdf, kids, Some(limits))?;
+            parents.push((id, limits.0.clone(), limits.1.clone()));
+            start += size;
+        }
+        nodes = parents;
+    }
+    write_kids(pdf, &nodes, None)
+}
+
+/// Split `n` items into as few groups of at most `NODE_SIZE` as possible,
+/// with sizes that differ by at most one.
+fn chunk_sizes(n: usize) -> Vec<usize> {
+    let groups = n.div_ceil(NODE_SIZE);
+    (0..groups)
+        .map(|i| n / groups + if i < n % groups { 1 } else { 0 })
+        .collect()
+}
+
+fn write_node(
+    pdf: &mut Pdf,
+    key: &str,
+    entries: &[Entry],
+    limits: Option<(&Vec<u8>, &Vec<u8>)>,
+) -> Result<usize> {
+    pdf.write_new_object(|object_id, pdf| {
+        write!(pdf.output, "<< {} [", key)?;
+        for (key, value) in entries {
+            write!(pdf.output, "\n   ")?;
+            pdf.output.write_all(key)?;
+            write!(pdf.output, " ")?;
+            pdf.output.write_all(value)?;
+        }
+        writeln!(pdf.output, "\n   ]")?;
+        write_limits(&mut pdf.output, limits)?;
+        writeln!(pdf.output, ">>")?;
+        Ok(object_id)
+    })
+}
+
+fn write_kids(
+    pdf: &mut Pdf,
+    kids: &[(usize, Vec<u8>, Vec<u8>)],
+    limits: Option<(&Vec<u8>, &Vec<u8>)>,
+) -> Result<usize> {
+    pdf.write_new_object(|object_id, pdf| {
+        write!(pdf.output, "<< /Kids [ ")?;
+        for kid in kids {
+            write!(pdf.output, "{} 0 R ", kid.0)?;
+        }
+        writeln!(pdf.output, "]")?;
+        write_limits(&mut pdf.output, limits)?;
+        writeln!(pdf.output, ">>")?;
+        Ok(object_id)
+    })
+}
+
+fn write_limits(
+    output: &mut dyn Write,
+    limits: Option<(&Vec<u8>, &Vec<u8>)>,
+) -> Result<()> {
+    if let Some((first, last)) = limits {
+        write!(output, "   /Limits [")?;
+        output.write_all(first)?;
+        write!(output, " ")?;
+        output.write_all(last)?;
+        writeln!(output, "]")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_chunk_sizes() {
+    assert_eq!(vec![1], chunk_sizes(1));
+    assert_eq!(vec![32], chunk_sizes(32));
+    assert_eq!(vec![17, 16], chunk_sizes(33));
+    assert_eq!(vec![25, 25, 25, 25], chunk_sizes(100));
+    assert_eq!(33, chunk_sizes(32 * 32 + 1).len());
+}
@@ -1,43 +1,127 @@
-pub use encoding::WIN_ANSI_ENCODING;
+use destination::Fit;
+use serialize::{write_literal_string, TextString};
+use std::collections::HashMap;
 use std::io::{Result, Write};
 
+/// Identifies an item in the document outline, so it can be used as the
+/// parent of other items.
+///
+/// Returned by `Canvas::add_outline` and related methods.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct OutlineId(pub(crate) usize);
+
 /// An item in the document outline.
 ///
 /// An OutlineItem associates a name (contained in an ordered tree) with a
-/// location in the document.  The PDF standard supports several ways to
-/// specify an exact location on a page, but this implementation currently only
-/// supports linking to a specific page.
+/// location on a page in the document.  Items without a parent are at the
+/// top level of the tree, other items are shown below their parent in the
+/// order they were added.
 ///
 /// To actually create an OutlineItem in a meaningful way, please use
-/// `Canvas::add_outline`.
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+/// `Canvas::add_outline_item` (or one of the simpler `Canvas::add_outline`
+/// and `Canvas::add_outline_at`).
+#[derive(Debug, PartialEq, Clone)]
 pub struct OutlineItem {
     title: String,
     page_id: Option<usize>,
+    fit: Fit,
+    destination_name: Option<String>,
+    parent: Option<OutlineId>,
+    open: bool,
+    italic: bool,
+    bold: bool,
+    color: Option<[f32; 3]>,
 }
 
 impl OutlineItem {
+    /// Create an outline item that shows the top left of its page, keeping
+    /// the current zoom.
     pub fn new(title: &str) -> OutlineItem {
         OutlineItem {
             title: title.to_string(),
             page_id: None,
+            fit: Fit::default(),
+            destination_name: None,
+            parent: None,
+            open: false,
+            italic: false,
+            bold: false,
+            color: None,
         }
     }
 
-    pub fn set_page(&mut self, page_id: usize) {
+    /// Set how the page is displayed when the item is activated.
+    pub fn set_fit(&mut self, fit: Fit) {
+        self.fit = fit;
+    }
+
+    /// Make the item point to the destination added with
+    /// `Canvas::add_named_destination` as `name`, instead of to the page it
+    /// is added on.
+    pub fn set_named_destination(&mut self, name: &str) {
+        self.destination_name = Some(name.to_string());
+    }
+
+    /// Make this item a child of `parent`.
+    pub fn set_parent(&mut self, parent: OutlineId) {
+        self.parent = Some(parent);
+    }
+
+    /// Set whether the children of this item are shown initially.  The
+    /// default is closed.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Show the title in italics, which requires PDF 1.4.
+    pub fn set_italic(&mut self, italic: bool) {
+        self.italic = italic;
+    }
+
+    /// Show the title in bold, which requires PDF 1.4.
+    pub fn set_bold(&mut self, bold: bool) {
+        self.bold = bold;
+    }
+
+    /// Show the title in the given RGB color, which requires PDF 1.4.
+    pub fn set_color(&mut self, red: u8, green: u8, blue: u8) {
+        self.color = Some([
+            f32::from(red) / 255.0,
+            f32::from(green) / 255.0,
+            f32::from(blue) / 255.0,
+        ]);
+    }
+
+    pub(crate) fn set_page(&mut self, page_id: usize) {
         self.page_id = Some(page_id)
     }
 
-    pub fn write_dictionary(
+    pub(crate) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub(crate) fn destination_name(&self) -> Option<&str> {
+        self.destination_name.as_deref()
+    }
+
+    /// Whether the item has style flags or a color, which require PDF 1.4.
+    pub(crate) fn is_styled(&self) -> bool {
+        self.italic || self.bold || self.color.is_some()
+    }
+
+    /// Write the item dictionary.  `first_last` are the first and last
+    /// children, and `count` the signed number of visible descendants, for
+    /// items that have children.
+    pub(crate) fn write_dictionary(
         &self,
-        output: &mut Write,
+        output: &mut dyn Write,
         parent_id: usize,
         prev: Option<usize>,
         next: Option<usize>,
+        first_last: Option<(usize, usize)>,
+        count: i64,
     ) -> Result<()> {
-        write!(output, "<< /Title (")?;
-        output.write_all(&WIN_ANSI_ENCODING.encode_string(&self.title))?;
-        writeln!(output, ")")?;
+        writeln!(output, "<< /Title {}", TextString(&self.title))?;
         writeln!(output, "/Parent {} 0 R", parent_id)?;
         if let Some(id) = prev {
             writeln!(output, "/Prev {} 0 R", id)?;
@@ -45,9 +129,122 @@ impl OutlineItem {
         if let Some(id) = next {
             writeln!(output, "/Next {} 0 R", id)?;
         }
-        if let Some(id) = self.page_id {
-            writeln!(output, "/Dest [{} 0 R /XYZ null null null]", id)?;
+        if let Some((first, last)) = first_last {
+            writeln!(output, "/First {} 0 R", first)?;
+            writeln!(output, "/Last {} 0 R", last)?;
+            writeln!(output, "/Count {}", count)?;
+        }
+        if let Some(ref name) = self.destination_name {
+            write!(output, "/Dest ")?;
+            write_literal_string(output, name.as_bytes())?;
+            writeln!(output)?;
+        } else if let Some(id) = self.page_id {
+            writeln!(output, "/Dest [{} 0 R {}]", id, self.fit)?;
+        }
+        let flags = self.italic as u8 | (self.bold as u8) << 1;
+        if flags != 0 {
+            writeln!(output, "/F {}", flags)?;
+        }
+        if let Some([r, g, b]) = self.color {
+            writeln!(output, "/C [{} {} {}]", r, g, b)?;
         }
         writeln!(output, ">>")
     }
 }
+
+/// The children of every item in `items`, by index, followed by the items
+/// at the top level.
+pub(crate) fn children(items: &[(OutlineId, OutlineItem)]) -> Vec<Vec<usize>> {
+    let index: HashMap<OutlineId, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(i, &(id, _))| (id, i))
+        .collect();
+    let mut children = vec![Vec::new(); items.len() + 1];
+    for (i, (_, item)) in items.iter().enumerate() {
+        // Items added while rendering a form are dropped, so a parent may
+        // be missing. Their children are put at the top level.
+        match item.parent.and_then(|p| index.get(&p)) {
+            Some(&p) if p < i => children[p].push(i),
+            _ => children[items.len()].push(i),
+        }
+    }
+    children
+}
+
+/// The number of visible descendants of every item when it is open, and of
+/// the top level, given the `children` of the items.
+pub(crate) fn visible_counts(
+    items: &[(OutlineId, OutlineItem)],
+    children: &[Vec<usize>],
+) -> Vec<i64> {
+    let mut counts = vec![0; children.len()];
+    // Children always come after their parent, so going backwards every
+    // count is complete before it is used for the parent. The top level is
+    // the last entry but depends on all the others.
+    let top = items.len();
+    for i in (0..top).rev().chain(Some(top)) {
+        counts[i] = children[i]
+            .iter()
+            .map(|&c| 1 + if items[c].1.open { counts[c] } else { 0 })
+            .sum();
+    }
+    counts
+}
+
+#[test]
+fn test_visible_counts() {
+    let mut items = vec![OutlineItem::new("a"); 5];
+    items[0].set_open(true);
+    items[1].set_parent(OutlineId(0));
+    items[2].set_parent(OutlineId(1));
+    items[3].set_parent(OutlineId(0));
+    // a (open) { b (closed) { c }, d }, e
+    let items: Vec<_> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| (OutlineId(i), item))
+        .collect();
+    let children = children(&items);
+    assert_eq!(
+        vec![vec![1, 3], vec![2], vec![], vec![], vec![], vec![0, 4]],
+        children
+    );
+    assert_eq!(vec![2, 1, 0, 0, 0, 4], visible_counts(&items, &children));
+}
+
+#[test]
+fn test_write_dictionary() {
+    let mut item = OutlineItem::new("Intro");
+    item.set_page(7);
+    item.set_fit(Fit::page());
+    item.set_bold(true);
+    item.set_italic(true);
+    item.set_color(255, 0, 0);
+    let mut output = Vec::new();
+    item.write_dictionary(&mut output, 2, None, Some(4), Some((5, 6)), -2)
+        .unwrap();
+    assert_eq!(
+        "<< /Title (Intro)\n/Parent 2 0 R\n/Next 4 0 R\n\
+         /First 5 0 R\n/Last 6 0 R\n/Count -2\n\
+         /Dest [7 0 R /Fit]\n/F 3\n/C [1 0 0]\n>>\n",
+        String::from_utf8(output).unwrap()
+    );
+}
+
+#[test]
+fn test_children_of_missing_parent() {
+    // The item with id 1 was added in a form and dropped, so its child
+    // moves to the top level instead of below the item with id 2.
+    let mut child = OutlineItem::new("c");
+    child.set_parent(OutlineId(1));
+    let items = vec![
+        (OutlineId(0), OutlineItem::new("a")),
+        (OutlineId(2), OutlineItem::new("b")),
+        (OutlineId(3), child),
+    ];
+    assert_eq!(
+        vec![vec![], vec![], vec![], vec![0, 1, 2]],
+        children(&items)
+    );
+}
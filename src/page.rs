@@ -0,0 +1,272 @@
+use std::io::{Error, ErrorKind, Result, Write};
+use units::{LengthUnit, UserSpace};
+
+/// The size, boxes, rotation and unit of a page, as described in section
+/// 14.11.2 of the PDF specification.
+///
+/// The media box is the whole page.  The optional crop box limits what is
+/// shown or printed, the bleed box is the region to clip to in production,
+/// the trim box is the size of the finished page and the art box is the
+/// meaningful content.  Every box must be inside the media box, and the trim
+/// box inside the bleed box.  The bleed box may extend beyond the crop box.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate simple_pdf;
+/// # use simple_pdf::units::{Points, UserSpace};
+/// # use simple_pdf::{PageSetup, Pdf};
+/// # use std::io;
+/// # fn main() -> io::Result<()> {
+/// # let mut document = Pdf::create("foo.pdf")?;
+/// // An A5 page with 3 mm bleed on each side.
+/// let (width, height, bleed) = (pt!(420), pt!(595), pt!(8.5));
+/// let (outer_width, outer_height) = (width + bleed * 2.0, height + bleed * 2.0);
+/// let mut setup = PageSetup::new(outer_width, outer_height);
+/// setup.set_bleed_box(pt!(0), pt!(0), outer_width, outer_height);
+/// setup.set_trim_box(bleed, bleed, width, height);
+/// document.render_page_with(&setup, |canvas| {
+///     // Draw up to the edge of the bleed box.
+///     Ok(())
+/// })?;
+/// # document.finish()
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct PageSetup {
+    media_box: [f32; 4],
+    crop_box: Option<[f32; 4]>,
+    bleed_box: Option<[f32; 4]>,
+    trim_box: Option<[f32; 4]>,
+    art_box: Option<[f32; 4]>,
+    rotate: u32,
+    user_unit: Option<f32>,
+}
+
+impl PageSetup {
+    /// Create a setup for a page of `width` × `height`, without other boxes
+    /// or rotation.
+    pub fn new<T: LengthUnit>(
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Self {
+        PageSetup {
+            media_box: [0.0, 0.0, width.pt, height.pt],
+            crop_box: None,
+            bleed_box: None,
+            trim_box: None,
+            art_box: None,
+            rotate: 0,
+            user_unit: None,
+        }
+    }
+
+    /// Set the media box to the rectangle with a corner at (x, y) extending
+    /// width × height.
+    pub fn set_media_box<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) {
+        self.media_box = rect(x, y, width, height);
+    }
+
+    /// Set the crop box to the rectangle with a corner at (x, y) extending
+    /// width × height.
+    pub fn set_crop_box<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) {
+        self.crop_box = Some(rect(x, y, width, height));
+    }
+
+    /// Set the bleed box to the rectangle with a corner at (x, y) extending
+    /// width × height.
+    pub fn set_bleed_box<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) {
+        self.bleed_box = Some(rect(x, y, width, height));
+    }
+
+    /// Set the trim box to the rectangle with a corner at (x, y) extending
+    /// width × height.
+    pub fn set_trim_box<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) {
+        self.trim_box = Some(rect(x, y, width, height));
+    }
+
+    /// Set the art box to the rectangle with a corner at (x, y) extending
+    /// width × height.
+    pub fn set_art_box<T: LengthUnit>(
+        &mut self,
+        x: UserSpace<T>,
+        y: UserSpace<T>,
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) {
+        self.art_box = Some(rect(x, y, width, height));
+    }
+
+    /// Rotate the page clockwise by `degrees` when it is displayed or
+    /// printed, e.g. 90 for a landscape page drawn in portrait orientation.
+    ///
+    /// Panics if `degrees` is not a multiple of 90.
+    pub fn set_rotation(&mut self, degrees: i32) {
+        assert!(degrees % 90 == 0, "pages can only be rotated by 90 degrees");
+        self.rotate = degrees.rem_euclid(360) as u32;
+    }
+
+    /// Set the size of a unit of user space to `user_unit` / 72 inch, for
+    /// pages larger than the 200 inches allowed with the default of 1.
+    /// Requires PDF 1.6.
+    ///
+    /// Panics if `user_unit` is not positive.
+    pub fn set_user_unit(&mut self, user_unit: f32) {
+        assert!(user_unit > 0.0, "the user unit must be positive");
+        self.user_unit = Some(user_unit);
+    }
+
+    pub(crate) fn user_unit(&self) -> Option<f32> {
+        self.user_unit
+    }
+
+    fn boxes(&self) -> [(&'static str, Option<[f32; 4]>); 5] {
+        [
+            ("MediaBox", Some(self.media_box)),
+            ("CropBox", self.crop_box),
+            ("BleedBox", self.bleed_box),
+            ("TrimBox", self.trim_box),
+            ("ArtBox", self.art_box),
+        ]
+    }
+
+    /// Check that the boxes are not empty and nest properly.
+    pub(crate) fn validate(&self) -> Result<()> {
+        for &(name, r) in &self.boxes() {
+            if let Some(r) = r {
+                if !(r[0] < r[2] && r[1] < r[3]) {
+                    return Err(invalid(format!("empty {}", name)));
+                }
+            }
+        }
+        let media = Some(self.media_box);
+        let containers = [
+            ("CropBox", self.crop_box, "MediaBox", media),
+            ("BleedBox", self.bleed_box, "MediaBox", media),
+            ("TrimBox", self.trim_box, "MediaBox", media),
+            ("ArtBox", self.art_box, "MediaBox", media),
+            ("TrimBox", self.trim_box, "BleedBox", self.bleed_box),
+        ];
+        for &(inner_name, inner, outer_name, outer) in &containers {
+            if let (Some(inner), Some(outer)) = (inner, outer) {
+                if !contains(outer, inner) {
+                    return Err(invalid(format!(
+                        "{} is not inside the {}",
+                        inner_name, outer_name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the entries of the page dictionary for the setup.
+    pub(crate) fn write_entries(&self, output: &mut dyn Write) -> Result<()> {
+        for &(name, r) in &self.boxes() {
+            if let Some(r) = r {
+                writeln!(
+                    output,
+                    "   /{} [{} {} {} {}]",
+                    name, r[0], r[1], r[2], r[3]
+                )?;
+            }
+        }
+        if self.rotate != 0 {
+            writeln!(output, "   /Rotate {}", self.rotate)?;
+        }
+        if let Some(user_unit) = self.user_unit {
+            writeln!(output, "   /UserUnit {}", user_unit)?;
+        }
+        Ok(())
+    }
+}
+
+fn rect<T: LengthUnit>(
+    x: UserSpace<T>,
+    y: UserSpace<T>,
+    width: UserSpace<T>,
+    height: UserSpace<T>,
+) -> [f32; 4] {
+    [x.pt, y.pt, (x + width).pt, (y + height).pt]
+}
+
+fn contains(outer: [f32; 4], inner: [f32; 4]) -> bool {
+    outer[0] <= inner[0]
+        && outer[1] <= inner[1]
+        && inner[2] <= outer[2]
+        && inner[3] <= outer[3]
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+use units::Points;
+
+#[test]
+fn test_validate_boxes() {
+    let mut setup = PageSetup::new(pt!(100), pt!(200));
+    setup.set_media_box(pt!(-10), pt!(-10), pt!(120), pt!(220));
+    setup.set_bleed_box(pt!(-5), pt!(-5), pt!(110), pt!(210));
+    setup.set_trim_box(pt!(0), pt!(0), pt!(100), pt!(200));
+    assert!(setup.validate().is_ok());
+
+    // A crop box at the trim box, with the bleed outside of it.
+    setup.set_crop_box(pt!(0), pt!(0), pt!(100), pt!(200));
+    assert!(setup.validate().is_ok());
+
+    setup.set_art_box(pt!(-20), pt!(0), pt!(100), pt!(200));
+    let err = setup.validate().unwrap_err();
+    assert_eq!("ArtBox is not inside the MediaBox", err.to_string());
+
+    setup.set_art_box(pt!(0), pt!(0), pt!(100), pt!(200));
+    setup.set_trim_box(pt!(-6), pt!(0), pt!(100), pt!(200));
+    let err = setup.validate().unwrap_err();
+    assert_eq!("TrimBox is not inside the BleedBox", err.to_string());
+
+    setup.set_art_box(pt!(0), pt!(0), pt!(0), pt!(10));
+    assert_eq!("empty ArtBox", setup.validate().unwrap_err().to_string());
+}
+
+#[test]
+fn test_write_entries() {
+    let mut setup = PageSetup::new(pt!(100), pt!(200));
+    setup.set_trim_box(pt!(10), pt!(10), pt!(80), pt!(180));
+    setup.set_rotation(-90);
+    setup.set_user_unit(2.5);
+    let mut output = Vec::new();
+    setup.write_entries(&mut output).unwrap();
+    assert_eq!(
+        "   /MediaBox [0 0 100 200]\n   \
+         /TrimBox [10 10 90 190]\n   \
+         /Rotate 270\n   \
+         /UserUnit 2.5\n",
+        String::from_utf8(output).unwrap()
+    );
+}
@@ -0,0 +1,110 @@
+use serialize::TextString;
+use std::io::{Result, Write};
+
+/// The numbering style of page labels.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PageLabelStyle {
+    /// Decimal arabic numerals: 1, 2, 3.
+    Decimal,
+    /// Uppercase roman numerals: I, II, III.
+    UpperRoman,
+    /// Lowercase roman numerals: i, ii, iii.
+    LowerRoman,
+    /// Uppercase letters: A to Z, then AA to ZZ, and so on.
+    UpperLetters,
+    /// Lowercase letters: a to z, then aa to zz, and so on.
+    LowerLetters,
+}
+
+/// How the pages of a range are labeled in a viewer, as described in
+/// section 12.4.2 of the PDF specification.
+///
+/// A label consists of an optional prefix followed by the page number in
+/// some style, e.g. "iv" or "A-2".
+///
+/// Set the label of a range of pages with `Pdf::set_page_label`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PageLabel {
+    style: Option<PageLabelStyle>,
+    prefix: String,
+    start: u32,
+}
+
+impl PageLabel {
+    /// Create labels numbered in `style`, starting at 1.
+    pub fn new(style: PageLabelStyle) -> Self {
+        PageLabel {
+            style: Some(style),
+            prefix: String::new(),
+            start: 1,
+        }
+    }
+
+    /// Create labels that are only the prefix, without a number.
+    pub fn prefix_only(prefix: &str) -> Self {
+        PageLabel {
+            style: None,
+            prefix: prefix.to_string(),
+            start: 1,
+        }
+    }
+
+    /// Set the prefix of the labels, e.g. "A-".
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.prefix = prefix.to_string();
+    }
+
+    /// Set the number of the first page in the range.
+    ///
+    /// Panics if `start` is 0.
+    pub fn set_start(&mut self, start: u32) {
+        assert!(start > 0, "page labels start at 1 or more");
+        self.start = start;
+    }
+
+    /// Write the page label dictionary.
+    pub(crate) fn write_dictionary(
+        &self,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        write!(output, "<<")?;
+        if let Some(style) = self.style {
+            let style = match style {
+                PageLabelStyle::Decimal => "D",
+                PageLabelStyle::UpperRoman => "R",
+                PageLabelStyle::LowerRoman => "r",
+                PageLabelStyle::UpperLetters => "A",
+                PageLabelStyle::LowerLetters => "a",
+            };
+            write!(output, " /S /{}", style)?;
+        }
+        if !self.prefix.is_empty() {
+            write!(output, " /P {}", TextString(&self.prefix))?;
+        }
+        if self.start != 1 {
+            write!(output, " /St {}", self.start)?;
+        }
+        write!(output, " >>")
+    }
+}
+
+#[test]
+fn test_write_page_label() {
+    let to_string = |label: &PageLabel| {
+        let mut output = Vec::new();
+        label.write_dictionary(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    assert_eq!(
+        "<< /S /r >>",
+        to_string(&PageLabel::new(PageLabelStyle::LowerRoman))
+    );
+    let mut appendix = PageLabel::new(PageLabelStyle::Decimal);
+    appendix.set_prefix("A-");
+    appendix.set_start(3);
+    assert_eq!("<< /S /D /P (A-) /St 3 >>", to_string(&appendix));
+    assert_eq!(
+        "<< /P (Cover) >>",
+        to_string(&PageLabel::prefix_only("Cover"))
+    );
+}
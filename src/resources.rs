@@ -0,0 +1,138 @@
+use fontref::FontRef;
+use fontsource::Font;
+use graphicsstate::{ColorSpace, ExtGState};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The named resources used by a content stream, e.g. the fonts, color
+/// spaces and graphics states used on a page.
+///
+/// Every resource gets a name that is unique within the content stream. The
+/// resource objects themselves are written after the content stream, when
+/// the page dictionary is created.
+#[derive(Debug, Default)]
+pub(crate) struct Resources {
+    pub fonts: HashMap<Font, FontRef>,
+    pub color_spaces: HashMap<ColorSpace, usize>,
+    /// Graphics state dictionaries, keyed by their written form.
+    pub ext_gstates: HashMap<String, usize>,
+    /// Patterns, keyed by their object id.
+    pub patterns: HashMap<usize, usize>,
+    /// Shadings, keyed by their object id.
+    pub shadings: HashMap<usize, usize>,
+    /// External objects, keyed by their object id.
+    pub xobjects: HashMap<usize, usize>,
+}
+
+impl Resources {
+    /// Get the name of a color space, registering it if it is used for the
+    /// first time.
+    pub fn color_space_name(&mut self, color_space: ColorSpace) -> String {
+        let next_n = self.color_spaces.len();
+        let n = *self.color_spaces.entry(color_space).or_insert(next_n);
+        format!("/CS{}", n)
+    }
+
+    /// Get the name of a graphics state parameter dictionary, registering it
+    /// if it is used for the first time.
+    pub fn ext_gstate_name(&mut self, ext_gstate: &ExtGState) -> String {
+        let next_n = self.ext_gstates.len();
+        let n = *self
+            .ext_gstates
+            .entry(ext_gstate.to_string())
+            .or_insert(next_n);
+        format!("/GS{}", n)
+    }
+
+    /// Get the name of a pattern that is already written to the document,
+    /// registering it if it is used for the first time.
+    pub fn pattern_name(&mut self, object_id: usize) -> String {
+        object_name(&mut self.patterns, "P", object_id)
+    }
+
+    /// Get the name of a shading that is already written to the document,
+    /// registering it if it is used for the first time.
+    pub fn shading_name(&mut self, object_id: usize) -> String {
+        object_name(&mut self.shadings, "Sh", object_id)
+    }
+
+    /// Get the name of an external object that is already written to the
+    /// document, registering it if it is used for the first time.
+    pub fn xobject_name(&mut self, object_id: usize) -> String {
+        object_name(&mut self.xobjects, "X", object_id)
+    }
+}
+
+fn object_name(
+    objects: &mut HashMap<usize, usize>,
+    prefix: &str,
+    object_id: usize,
+) -> String {
+    let next_n = objects.len();
+    let n = *objects.entry(object_id).or_insert(next_n);
+    format!("/{}{}", prefix, n)
+}
+
+/// Resource names mapped to object ids, written as the inner part of a
+/// resource sub-dictionary.
+pub(crate) struct NamedRefs {
+    oids: HashMap<String, usize>,
+}
+
+impl NamedRefs {
+    pub fn with_capacity(capacity: usize) -> Self {
+        NamedRefs {
+            oids: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn insert(&mut self, name: String, object_id: usize) -> Option<usize> {
+        self.oids.insert(name, object_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.oids.is_empty()
+    }
+}
+
+impl fmt::Display for NamedRefs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, id) in &self.oids {
+            write!(f, "{} {} 0 R ", name, id)?;
+        }
+        Ok(())
+    }
+}
+
+/// The object ids of all resources of a content stream, written as a
+/// resource dictionary.
+pub(crate) struct ResourceRefs {
+    pub fonts: NamedRefs,
+    pub color_spaces: NamedRefs,
+    pub ext_gstates: NamedRefs,
+    pub patterns: NamedRefs,
+    pub shadings: NamedRefs,
+    pub xobjects: NamedRefs,
+}
+
+impl fmt::Display for ResourceRefs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<< /Font << {}>>", self.fonts)?;
+        if !self.color_spaces.is_empty() {
+            write!(f, " /ColorSpace << {}>>", self.color_spaces)?;
+        }
+        if !self.ext_gstates.is_empty() {
+            write!(f, " /ExtGState << {}>>", self.ext_gstates)?;
+        }
+        if !self.patterns.is_empty() {
+            write!(f, " /Pattern << {}>>", self.patterns)?;
+        }
+        if !self.shadings.is_empty() {
+            write!(f, " /Shading << {}>>", self.shadings)?;
+        }
+        if !self.xobjects.is_empty() {
+            write!(f, " /XObject << {}>>", self.xobjects)?;
+        }
+        write!(f, " >>")
+    }
+}
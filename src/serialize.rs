@@ -0,0 +1,297 @@
+//! Helpers for writing PDF syntax elements like names and numbers.
+use std::fmt;
+use std::io::{Result, Write};
+
+/// A PDF name object, written with a leading slash.
+///
+/// Characters outside the regular printable ASCII range and delimiters are
+/// written as `#xx` escapes, as described in section 7.3.5 of the PDF
+/// specification.
+pub(crate) struct Name<'a>(pub &'a str);
+
+impl<'a> fmt::Display for Name<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "/")?;
+        for &byte in self.0.as_bytes() {
+            match byte {
+                b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}'
+                | b'/' | b'%' | b'#' => write!(f, "#{:02X}", byte)?,
+                0x21..=0x7E => write!(f, "{}", byte as char)?,
+                _ => write!(f, "#{:02X}", byte)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A real number written with at most the given number of decimal places and
+/// without trailing zeros, so the output is compact and does not depend on
+/// the rounding noise of floating point calculations.
+pub(crate) struct Real(pub f32, pub usize);
+
+impl fmt::Display for Real {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = format!("{:.*}", self.1, self.0);
+        let s = if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &s
+        };
+        if s == "-0" {
+            write!(f, "0")
+        } else {
+            write!(f, "{}", s)
+        }
+    }
+}
+
+/// Write `bytes` as a PDF literal string, escaping backslashes, parentheses
+/// and control characters, as described in section 7.3.4.2 of the PDF
+/// specification.  Other bytes are written unchanged.
+pub(crate) fn write_literal_string(
+    output: &mut dyn Write,
+    bytes: &[u8],
+) -> Result<()> {
+    let mut escaped = Vec::with_capacity(bytes.len() + 2);
+    escaped.push(b'(');
+    for &byte in bytes {
+        match byte {
+            b'\\' | b'(' | b')' => escaped.extend_from_slice(&[b'\\', byte]),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            b'\t' => escaped.extend_from_slice(b"\\t"),
+            0x08 => escaped.extend_from_slice(b"\\b"),
+            0x0C => escaped.extend_from_slice(b"\\f"),
+            0x00..=0x1F | 0x7F => {
+                write!(escaped, "\\{:03o}", byte)?;
+            }
+            _ => escaped.push(byte),
+        }
+    }
+    escaped.push(b')');
+    output.write_all(&escaped)
+}
+
+/// Write `bytes` as a PDF hexadecimal string.
+pub(crate) fn write_hex_string(
+    output: &mut dyn Write,
+    bytes: &[u8],
+) -> Result<()> {
+    write!(output, "<")?;
+    for byte in bytes {
+        write!(output, "{:02X}", byte)?;
+    }
+    write!(output, ">")
+}
+
+/// A PDF text string, used for outline titles, document information and
+/// other text that is shown to the user rather than drawn on a page.
+///
+/// Text that can be represented in PDFDocEncoding is written as a literal
+/// string, escaping delimiters, control characters and bytes outside the
+/// ASCII range.  Other text is written as a hexadecimal string in UTF-16BE
+/// with a byte order mark, as described in section 7.9.2.2 of the PDF
+/// specification.
+pub(crate) struct TextString<'a>(pub &'a str);
+
+impl<'a> fmt::Display for TextString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let encoded: Option<Vec<u8>> =
+            self.0.chars().map(pdf_doc_encode).collect();
+        match encoded {
+            Some(bytes) => {
+                write!(f, "(")?;
+                for byte in bytes {
+                    match byte {
+                        b'\\' | b'(' | b')' => write!(f, "\\{}", byte as char)?,
+                        b'\n' => write!(f, "\\n")?,
+                        b'\r' => write!(f, "\\r")?,
+                        b'\t' => write!(f, "\\t")?,
+                        0x20..=0x7E => write!(f, "{}", byte as char)?,
+                        _ => write!(f, "\\{:03o}", byte)?,
+                    }
+                }
+                write!(f, ")")
+            }
+            None => {
+                write!(f, "<FEFF")?;
+                for unit in self.0.encode_utf16() {
+                    write!(f, "{:04X}", unit)?;
+                }
+                write!(f, ">")
+            }
+        }
+    }
+}
+
+/// The PDFDocEncoding code of `ch`, if it has one.
+fn pdf_doc_encode(ch: char) -> Option<u8> {
+    // Codes 0x18 to 0x1F and 0x80 to 0xA0 differ from Unicode. Of the
+    // control characters, only tab, line feed and carriage return are
+    // defined.
+    const SPECIAL: [(u8, char); 40] = [
+        (0x18, '\u{02D8}'),
+        (0x19, '\u{02C7}'),
+        (0x1A, '\u{02C6}'),
+        (0x1B, '\u{02D9}'),
+        (0x1C, '\u{02DD}'),
+        (0x1D, '\u{02DB}'),
+        (0x1E, '\u{02DA}'),
+        (0x1F, '\u{02DC}'),
+        (0x80, '\u{2022}'),
+        (0x81, '\u{2020}'),
+        (0x82, '\u{2021}'),
+        (0x83, '\u{2026}'),
+        (0x84, '\u{2014}'),
+        (0x85, '\u{2013}'),
+        (0x86, '\u{0192}'),
+        (0x87, '\u{2044}'),
+        (0x88, '\u{2039}'),
+        (0x89, '\u{203A}'),
+        (0x8A, '\u{2212}'),
+        (0x8B, '\u{2030}'),
+        (0x8C, '\u{201E}'),
+        (0x8D, '\u{201C}'),
+        (0x8E, '\u{201D}'),
+        (0x8F, '\u{2018}'),
+        (0x90, '\u{2019}'),
+        (0x91, '\u{201A}'),
+        (0x92, '\u{2122}'),
+        (0x93, '\u{FB01}'),
+        (0x94, '\u{FB02}'),
+        (0x95, '\u{0141}'),
+        (0x96, '\u{0152}'),
+        (0x97, '\u{0160}'),
+        (0x98, '\u{0178}'),
+        (0x99, '\u{017D}'),
+        (0x9A, '\u{0131}'),
+        (0x9B, '\u{0142}'),
+        (0x9C, '\u{0153}'),
+        (0x9D, '\u{0161}'),
+        (0x9E, '\u{017E}'),
+        (0xA0, '\u{20AC}'),
+    ];
+    let code = ch as u32;
+    match code {
+        0x09 | 0x0A | 0x0D | 0x20..=0x7E | 0xA1..=0xAC | 0xAE..=0xFF => {
+            Some(code as u8)
+        }
+        _ => SPECIAL.iter().find(|&&(_, c)| c == ch).map(|&(b, _)| b),
+    }
+}
+
+#[test]
+fn test_name_escaping() {
+    assert_eq!("/DeviceRGB", Name("DeviceRGB").to_string());
+    assert_eq!("/PANTONE#20185#20C", Name("PANTONE 185 C").to_string());
+    assert_eq!("/A#23B#2FC", Name("A#B/C").to_string());
+    assert_eq!("/K#C3#B6ln", Name("Köln").to_string());
+}
+
+#[test]
+fn test_text_string() {
+    assert_eq!("(Title)", TextString("Title").to_string());
+    assert_eq!(
+        "(Smith \\(Jr.\\) \\\\ co\\n\\t)",
+        TextString("Smith (Jr.) \\ co\n\t").to_string()
+    );
+    assert_eq!("(K\\366ln \\240 \\223)", TextString("Köln € ﬁ").to_string());
+    assert_eq!("<FEFF4E2D6587>", TextString("中文").to_string());
+    assert_eq!("<FEFFD83DDE00>", TextString("\u{1F600}").to_string());
+    // Codes that are not defined in PDFDocEncoding.
+    assert_eq!("<FEFF00AD>", TextString("\u{AD}").to_string());
+    assert_eq!("<FEFF00410001>", TextString("A\u{1}").to_string());
+    assert_eq!("<FEFF007F>", TextString("\u{7F}").to_string());
+}
+
+/// Parse a literal or hexadecimal string the way a PDF reader does.
+#[cfg(test)]
+fn parse_string(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    if data[0] == b'<' {
+        let digits: Vec<u8> = data[1..data.len() - 1]
+            .iter()
+            .map(|&d| (d as char).to_digit(16).unwrap() as u8)
+            .collect();
+        for pair in digits.chunks(2) {
+            result.push(pair[0] << 4 | pair.get(1).unwrap_or(&0));
+        }
+        return result;
+    }
+    assert_eq!((b'(', b')'), (data[0], data[data.len() - 1]));
+    let data = &data[1..data.len() - 1];
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'\\' => {
+                i += 1;
+                match data[i] {
+                    b'n' => result.push(b'\n'),
+                    b'r' => result.push(b'\r'),
+                    b't' => result.push(b'\t'),
+                    b'b' => result.push(0x08),
+                    b'f' => result.push(0x0C),
+                    b'0'..=b'7' => {
+                        let mut code = 0u32;
+                        let start = i;
+                        while i < data.len()
+                            && i < start + 3
+                            && (b'0'..=b'7').contains(&data[i])
+                        {
+                            code = code * 8 + u32::from(data[i] - b'0');
+                            i += 1;
+                        }
+                        result.push(code as u8);
+                        continue;
+                    }
+                    c => result.push(c),
+                }
+            }
+            // An unescaped end of line is read as a line feed.
+            b'\r' => {
+                result.push(b'\n');
+                if data.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+            }
+            b'(' | b')' => panic!("unbalanced parenthesis"),
+            c => result.push(c),
+        }
+        i += 1;
+    }
+    result
+}
+
+#[test]
+fn test_literal_string() {
+    let mut output = Vec::new();
+    write_literal_string(&mut output, b"a(b)\\c\r\n\x00\xE9").unwrap();
+    assert_eq!(&b"(a\\(b\\)\\\\c\\r\\n\\000\xE9)"[..], &output[..]);
+}
+
+#[test]
+fn test_strings_round_trip_all_bytes() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    for &write in &[write_literal_string, write_hex_string] {
+        let mut output = Vec::new();
+        write(&mut output, &bytes).unwrap();
+        assert_eq!(bytes, parse_string(&output));
+        // Every byte on its own and repeated, so escapes are not merged
+        // with what follows them.
+        for &byte in &bytes {
+            let mut output = Vec::new();
+            write(&mut output, &[byte, byte, b'7']).unwrap();
+            assert_eq!(vec![byte, byte, b'7'], parse_string(&output));
+        }
+    }
+}
+
+#[test]
+fn test_real_precision() {
+    assert_eq!("0.5", Real(0.5, 4).to_string());
+    assert_eq!("0.502", Real(128.0 / 255.0, 4).to_string());
+    assert_eq!("1", Real(0.999_999, 4).to_string());
+    assert_eq!("0", Real(-0.000_01, 4).to_string());
+    assert_eq!("-12.25", Real(-12.25, 4).to_string());
+    assert_eq!("300", Real(300.0, 0).to_string());
+}
@@ -1,9 +1,10 @@
 use encoding::{get_base_enc, Encoding};
 use fontref::FontRef;
-use graphicsstate::Color;
+use graphicsstate::{write_color, Color};
+use resources::Resources;
+use serialize::{write_hex_string, write_literal_string};
 use std::fmt;
-use std::fs::File;
-use std::io::{BufWriter, Result, Write};
+use std::io::{Result, Write};
 use units::{LengthUnit, UserSpace};
 
 /// A text object is where text is put on the canvas.
@@ -41,16 +42,23 @@ use units::{LengthUnit, UserSpace};
 /// # }
 /// ```
 pub struct TextObject<'a> {
-    output: &'a mut BufWriter<File>,
+    output: &'a mut Vec<u8>,
+    resources: &'a mut Resources,
     encoding: Encoding,
+    hex_strings: bool,
 }
 
 impl<'a> TextObject<'a> {
     // Should not be called by user code.
-    pub(crate) fn new(output: &'a mut BufWriter<File>) -> Self {
+    pub(crate) fn new(
+        output: &'a mut Vec<u8>,
+        resources: &'a mut Resources,
+    ) -> Self {
         TextObject {
             output,
+            resources,
             encoding: get_base_enc().to_encoding().clone(),
+            hex_strings: false,
         }
     }
     /// Set the font and font-size to be used by the following text operations.
@@ -100,17 +108,11 @@ impl<'a> TextObject<'a> {
 
     /// Set color for stroking operations.
     pub fn set_stroke_color(&mut self, color: Color) -> Result<()> {
-        match color {
-            Color::RGB { .. } => writeln!(self.output, "{} SC", color),
-            Color::Gray { .. } => writeln!(self.output, "{} G", color),
-        }
+        write_color(self.output, self.resources, &color, true)
     }
     /// Set color for non-stroking operations.
     pub fn set_fill_color(&mut self, color: Color) -> Result<()> {
-        match color {
-            Color::RGB { .. } => writeln!(self.output, "{} sc", color),
-            Color::Gray { .. } => writeln!(self.output, "{} g", color),
-        }
+        write_color(self.output, self.resources, &color, false)
     }
 
     /// Move text position.
@@ -127,9 +129,8 @@ impl<'a> TextObject<'a> {
     }
     /// Show a text.
     pub fn show(&mut self, text: &str) -> Result<()> {
-        write!(self.output, "(")?;
-        self.output.write_all(&self.encoding.encode_string(text))?;
-        writeln!(self.output, ") Tj")
+        self.write_string(text)?;
+        writeln!(self.output, " Tj")
     }
 
     /// Show one or more text strings, allowing individual glyph positioning.
@@ -163,17 +164,21 @@ impl<'a> TextObject<'a> {
     pub fn show_adjusted(&mut self, param: &[(&str, i32)]) -> Result<()> {
         write!(self.output, "[")?;
         for &(text, offset) in param {
-            write!(self.output, "(")?;
-            self.output.write_all(&self.encoding.encode_string(text))?;
-            write!(self.output, ") {} ", offset)?;
+            self.write_string(text)?;
+            write!(self.output, " {} ", offset)?;
         }
         writeln!(self.output, "] TJ")
     }
     /// Show a text as a line.  See also [set_leading](#method.set_leading).
     pub fn show_line(&mut self, text: &str) -> Result<()> {
-        write!(self.output, "(")?;
-        self.output.write_all(&self.encoding.encode_string(text))?;
-        writeln!(self.output, ") '")
+        self.write_string(text)?;
+        writeln!(self.output, " '")
+    }
+    /// Write the strings of the following text operations as hexadecimal
+    /// strings (`<...>`) instead of literal strings (`(...)`).  This is
+    /// mostly useful for inspecting the codes used for each character.
+    pub fn set_hex_strings(&mut self, hex_strings: bool) {
+        self.hex_strings = hex_strings;
     }
     /// Push the graphics state on a stack.
     pub fn gsave(&mut self) -> Result<()> {
@@ -186,6 +191,15 @@ impl<'a> TextObject<'a> {
         // TODO Pop current encoding in self?
         writeln!(self.output, "Q")
     }
+
+    fn write_string(&mut self, text: &str) -> Result<()> {
+        let bytes = self.encoding.encode_bytes(text);
+        if self.hex_strings {
+            write_hex_string(self.output, &bytes)
+        } else {
+            write_literal_string(self.output, &bytes)
+        }
+    }
 }
 
 /// Text rendering modes for the method 
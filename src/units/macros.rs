@@ -17,7 +17,6 @@
 macro_rules! newUnit {
     ($new_unit:ty, $nm_conv:expr) => {
         impl LengthUnit for $new_unit {
-            #[inline(always)]
             const PT_IN_UNIT: f32 = $nm_conv;
         }
     };
@@ -18,6 +18,9 @@
 #[macro_use]
 mod macros;
 
+mod pagesize;
+pub use self::pagesize::{Orientation, PageSize};
+
 use std::cmp::Ordering;
 use std::fmt;
 use std::marker::PhantomData;
@@ -40,6 +43,13 @@ pub struct UserSpace<T: LengthUnit> {
 }
 
 impl<T: LengthUnit> UserSpace<T> {
+    /// Create a length of `pt` points, in unit `T`.
+    pub(crate) fn from_pt(pt: f32) -> Self {
+        UserSpace {
+            pt,
+            unit: PhantomData,
+        }
+    }
     /// Returns absoulute value as floating point.
     pub fn abs(self) -> f32 {
         self.pt.abs()
@@ -56,6 +66,20 @@ impl<T: LengthUnit> UserSpace<T> {
     pub fn tan(self) -> f32 {
         self.pt.tan()
     }
+    /// Returns the same length in another unit.
+    /// # Example
+    /// ````
+    /// #[macro_use]
+    /// extern crate simple_pdf;
+    /// use simple_pdf::units::{Inches, Millimeters, UserSpace};
+    /// # fn main() {
+    /// let length = mm!(50.8).to::<Inches>();
+    /// assert_eq!(2.0, f32::from(length).round());
+    /// # }
+    /// ````
+    pub fn to<T2: LengthUnit>(self) -> UserSpace<T2> {
+        UserSpace::from(&self)
+    }
 }
 
 /// Trait for implementing units.
@@ -80,6 +104,30 @@ pub struct Millimeters;
 /// Representation of the unit points which is used widely in printing.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Points;
+/// Representation of the metric unit centimeter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centimeters;
+/// Representation of the imperial unit inch, which is 72 points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inches;
+/// Representation of the typographic unit pica, which is 12 points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Picas;
+/// Representation of the Didot point of continental European typography,
+/// which is 0.376 mm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Didot;
+/// Representation of the cicero, which is 12 Didot points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cicero;
+/// Representation of pixels of an image or screen with a resolution of
+/// `DPI` dots per inch. The default of 96 is the pixel of CSS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pixels<const DPI: u32 = 96>;
+
+impl<const DPI: u32> LengthUnit for Pixels<DPI> {
+    const PT_IN_UNIT: f32 = 72.0 / DPI as f32;
+}
 
 /// Creates from number a value in millimeters. Using this macro requires
 /// importing [Millimeters](units/struct.Millimeters.html).
@@ -121,6 +169,112 @@ macro_rules! pt {
     };
 }
 
+/// Creates from number a value in centimeters. Using this macro requires
+/// importing [Centimeters](units/struct.Centimeters.html).
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Centimeters, Millimeters, UserSpace};
+/// # fn main() {
+/// assert_eq!(cm!(3), mm!(30));
+/// # }
+/// ````
+#[macro_export]
+macro_rules! cm {
+    ($num:expr) => {
+        UserSpace::<Centimeters>::from(&$num)
+    };
+}
+
+/// Creates from number a value in inches. Using this macro requires importing
+/// [Inches](units/struct.Inches.html).
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Inches, Points, UserSpace};
+/// # fn main() {
+/// assert_eq!(inch!(0.5), pt!(36));
+/// # }
+/// ````
+#[macro_export]
+macro_rules! inch {
+    ($num:expr) => {
+        UserSpace::<Inches>::from(&$num)
+    };
+}
+
+/// Creates from number a value in picas. Using this macro requires importing
+/// [Picas](units/struct.Picas.html).
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Picas, Points, UserSpace};
+/// # fn main() {
+/// assert_eq!(pc!(3), pt!(36));
+/// # }
+/// ````
+#[macro_export]
+macro_rules! pc {
+    ($num:expr) => {
+        UserSpace::<Picas>::from(&$num)
+    };
+}
+
+/// Creates from number a value in Didot points. Using this macro requires
+/// importing [Didot](units/struct.Didot.html).
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Cicero, Didot, UserSpace};
+/// # fn main() {
+/// assert_eq!(dd!(12), cc!(1));
+/// # }
+/// ````
+#[macro_export]
+macro_rules! dd {
+    ($num:expr) => {
+        UserSpace::<Didot>::from(&$num)
+    };
+}
+
+/// Creates from number a value in ciceros. Using this macro requires
+/// importing [Cicero](units/struct.Cicero.html).
+#[macro_export]
+macro_rules! cc {
+    ($num:expr) => {
+        UserSpace::<Cicero>::from(&$num)
+    };
+}
+
+/// Creates from number a value in pixels, at 96 dots per inch or at the
+/// resolution given as second argument. Using this macro requires importing
+/// [Pixels](units/struct.Pixels.html).
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Inches, Pixels, UserSpace};
+/// # fn main() {
+/// assert_eq!(px!(96), inch!(1));
+/// assert_eq!(px!(300, 300), inch!(1));
+/// # }
+/// ````
+#[macro_export]
+macro_rules! px {
+    ($num:expr) => {
+        UserSpace::<Pixels>::from(&$num)
+    };
+    ($num:expr, $dpi:expr) => {
+        UserSpace::<Pixels<{ $dpi }>>::from(&$num)
+    };
+}
+
+/// Converts a length to another unit. Lengths are stored in points, so the
+/// converted value is the same length, e.g. 25.4 mm become 1 inch.
 impl<'a, T1, T2> From<&'a UserSpace<T1>> for UserSpace<T2>
 where
     T1: LengthUnit,
@@ -213,6 +367,11 @@ where
 
 newUnit!(Millimeters, 2.834_646);
 newUnit!(Points, 1.0);
+newUnit!(Centimeters, 10.0 * Millimeters::PT_IN_UNIT);
+newUnit!(Inches, 72.0);
+newUnit!(Picas, 12.0);
+newUnit!(Didot, 1.065_826);
+newUnit!(Cicero, 12.0 * Didot::PT_IN_UNIT);
 
 implFromUserSpace!(f64);
 implFromUserSpace!(i64);
@@ -225,3 +384,25 @@ implMulAndDiv!(f64);
 implMulAndDiv!(i32);
 implMulAndDiv!(f32);
 implMulAndDiv!(isize);
+
+#[test]
+fn test_conversion_keeps_length() {
+    let close = |expected: f32, length: f32| {
+        assert!(
+            (expected - length).abs() < 1e-4,
+            "{} != {}",
+            expected,
+            length
+        )
+    };
+    let length = UserSpace::<Inches>::from(1);
+    close(25.4, f32::from(length.to::<Millimeters>()));
+    close(2.54, f32::from(length.to::<Centimeters>()));
+    close(6.0, f32::from(length.to::<Picas>()));
+    close(72.0, f32::from(length.to::<Points>()));
+    close(300.0, f32::from(length.to::<Pixels<300>>()));
+    close(1.0, f32::from(length.to::<Millimeters>().to::<Inches>()));
+    let converted: UserSpace<Pixels> = UserSpace::from(&length);
+    close(96.0, f32::from(converted));
+    close(12.0, f32::from(UserSpace::<Cicero>::from(1).to::<Didot>()));
+}
@@ -0,0 +1,233 @@
+use super::{LengthUnit, Millimeters, UserSpace};
+use std::marker::PhantomData;
+
+/// The orientation of a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Higher than wide.
+    Portrait,
+    /// Wider than high.
+    Landscape,
+}
+
+/// The size of a page, with constants for standard paper sizes.
+///
+/// The constants are in portrait orientation, use
+/// [landscape](#method.landscape) to turn them.
+///
+/// # Example
+/// ````
+/// #[macro_use]
+/// extern crate simple_pdf;
+/// use simple_pdf::units::{Millimeters, PageSize, Points, UserSpace};
+/// # fn main() {
+/// let (width, height) = PageSize::A4.size();
+/// assert_eq!((mm!(210), mm!(297)), (width, height));
+/// let (width, height): (UserSpace<Points>, UserSpace<Points>) =
+///     PageSize::LETTER.landscape().size();
+/// assert_eq!((pt!(792), pt!(612)), (width, height));
+/// # }
+/// ````
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSize {
+    width: f32,
+    height: f32,
+}
+
+const MM: f32 = <Millimeters as LengthUnit>::PT_IN_UNIT;
+const INCH: f32 = 72.0;
+
+const fn mm(width: f32, height: f32) -> PageSize {
+    PageSize {
+        width: width * MM,
+        height: height * MM,
+    }
+}
+
+const fn inches(width: f32, height: f32) -> PageSize {
+    PageSize {
+        width: width * INCH,
+        height: height * INCH,
+    }
+}
+
+impl PageSize {
+    /// ISO 216 A0, 841 × 1189 mm.
+    pub const A0: PageSize = mm(841.0, 1189.0);
+    /// ISO 216 A1, 594 × 841 mm.
+    pub const A1: PageSize = mm(594.0, 841.0);
+    /// ISO 216 A2, 420 × 594 mm.
+    pub const A2: PageSize = mm(420.0, 594.0);
+    /// ISO 216 A3, 297 × 420 mm.
+    pub const A3: PageSize = mm(297.0, 420.0);
+    /// ISO 216 A4, 210 × 297 mm.
+    pub const A4: PageSize = mm(210.0, 297.0);
+    /// ISO 216 A5, 148 × 210 mm.
+    pub const A5: PageSize = mm(148.0, 210.0);
+    /// ISO 216 A6, 105 × 148 mm.
+    pub const A6: PageSize = mm(105.0, 148.0);
+    /// ISO 216 A7, 74 × 105 mm.
+    pub const A7: PageSize = mm(74.0, 105.0);
+    /// ISO 216 A8, 52 × 74 mm.
+    pub const A8: PageSize = mm(52.0, 74.0);
+    /// ISO 216 A9, 37 × 52 mm.
+    pub const A9: PageSize = mm(37.0, 52.0);
+    /// ISO 216 A10, 26 × 37 mm.
+    pub const A10: PageSize = mm(26.0, 37.0);
+
+    /// ISO 216 B0, 1000 × 1414 mm.
+    pub const B0: PageSize = mm(1000.0, 1414.0);
+    /// ISO 216 B1, 707 × 1000 mm.
+    pub const B1: PageSize = mm(707.0, 1000.0);
+    /// ISO 216 B2, 500 × 707 mm.
+    pub const B2: PageSize = mm(500.0, 707.0);
+    /// ISO 216 B3, 353 × 500 mm.
+    pub const B3: PageSize = mm(353.0, 500.0);
+    /// ISO 216 B4, 250 × 353 mm.
+    pub const B4: PageSize = mm(250.0, 353.0);
+    /// ISO 216 B5, 176 × 250 mm.
+    pub const B5: PageSize = mm(176.0, 250.0);
+    /// ISO 216 B6, 125 × 176 mm.
+    pub const B6: PageSize = mm(125.0, 176.0);
+    /// ISO 216 B7, 88 × 125 mm.
+    pub const B7: PageSize = mm(88.0, 125.0);
+    /// ISO 216 B8, 62 × 88 mm.
+    pub const B8: PageSize = mm(62.0, 88.0);
+    /// ISO 216 B9, 44 × 62 mm.
+    pub const B9: PageSize = mm(44.0, 62.0);
+    /// ISO 216 B10, 31 × 44 mm.
+    pub const B10: PageSize = mm(31.0, 44.0);
+
+    /// ISO 269 C0 envelope, 917 × 1297 mm.
+    pub const C0: PageSize = mm(917.0, 1297.0);
+    /// ISO 269 C1 envelope, 648 × 917 mm.
+    pub const C1: PageSize = mm(648.0, 917.0);
+    /// ISO 269 C2 envelope, 458 × 648 mm.
+    pub const C2: PageSize = mm(458.0, 648.0);
+    /// ISO 269 C3 envelope, 324 × 458 mm.
+    pub const C3: PageSize = mm(324.0, 458.0);
+    /// ISO 269 C4 envelope, 229 × 324 mm.
+    pub const C4: PageSize = mm(229.0, 324.0);
+    /// ISO 269 C5 envelope, 162 × 229 mm.
+    pub const C5: PageSize = mm(162.0, 229.0);
+    /// ISO 269 C6 envelope, 114 × 162 mm.
+    pub const C6: PageSize = mm(114.0, 162.0);
+    /// ISO 269 C7 envelope, 81 × 114 mm.
+    pub const C7: PageSize = mm(81.0, 114.0);
+    /// ISO 269 C8 envelope, 57 × 81 mm.
+    pub const C8: PageSize = mm(57.0, 81.0);
+    /// ISO 269 C9 envelope, 40 × 57 mm.
+    pub const C9: PageSize = mm(40.0, 57.0);
+    /// ISO 269 C10 envelope, 28 × 40 mm.
+    pub const C10: PageSize = mm(28.0, 40.0);
+    /// ISO 269 DL envelope, 110 × 220 mm.
+    pub const DL: PageSize = mm(110.0, 220.0);
+
+    /// US Letter, 8.5 × 11 inches.
+    pub const LETTER: PageSize = inches(8.5, 11.0);
+    /// US Legal, 8.5 × 14 inches.
+    pub const LEGAL: PageSize = inches(8.5, 14.0);
+    /// US Tabloid, 11 × 17 inches.  In landscape orientation it is known as
+    /// Ledger.
+    pub const TABLOID: PageSize = inches(11.0, 17.0);
+    /// US Executive, 7.25 × 10.5 inches.
+    pub const EXECUTIVE: PageSize = inches(7.25, 10.5);
+    /// US Number 10 envelope, 4.125 × 9.5 inches.
+    pub const ENVELOPE_10: PageSize = inches(4.125, 9.5);
+    /// US Monarch envelope, 3.875 × 7.5 inches.
+    pub const ENVELOPE_MONARCH: PageSize = inches(3.875, 7.5);
+
+    /// Photo print, 4 × 6 inches.
+    pub const PHOTO_4X6: PageSize = inches(4.0, 6.0);
+    /// Photo print, 5 × 7 inches.
+    pub const PHOTO_5X7: PageSize = inches(5.0, 7.0);
+    /// Photo print, 8 × 10 inches.
+    pub const PHOTO_8X10: PageSize = inches(8.0, 10.0);
+
+    /// Create a page size of `width` × `height`.
+    pub fn new<T: LengthUnit>(
+        width: UserSpace<T>,
+        height: UserSpace<T>,
+    ) -> Self {
+        PageSize {
+            width: width.pt,
+            height: height.pt,
+        }
+    }
+
+    /// The width of the page.
+    pub fn width<T: LengthUnit>(&self) -> UserSpace<T> {
+        UserSpace {
+            pt: self.width,
+            unit: PhantomData,
+        }
+    }
+
+    /// The height of the page.
+    pub fn height<T: LengthUnit>(&self) -> UserSpace<T> {
+        UserSpace {
+            pt: self.height,
+            unit: PhantomData,
+        }
+    }
+
+    /// The width and height of the page, as taken by `Pdf::render_page`.
+    pub fn size<T: LengthUnit>(&self) -> (UserSpace<T>, UserSpace<T>) {
+        (self.width(), self.height())
+    }
+
+    /// The orientation of the page.  Square pages are in portrait
+    /// orientation.
+    pub fn orientation(&self) -> Orientation {
+        if self.width > self.height {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+
+    /// Return the size in `orientation`, swapping width and height if
+    /// needed.
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
+        if self.orientation() == orientation {
+            self
+        } else {
+            self.flipped()
+        }
+    }
+
+    /// Return the size in portrait orientation.
+    pub fn portrait(self) -> Self {
+        self.with_orientation(Orientation::Portrait)
+    }
+
+    /// Return the size in landscape orientation.
+    pub fn landscape(self) -> Self {
+        self.with_orientation(Orientation::Landscape)
+    }
+
+    /// Return the size with width and height swapped.
+    pub fn flipped(self) -> Self {
+        PageSize {
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+#[test]
+fn test_page_sizes() {
+    use units::Points;
+    let (width, height) = PageSize::A4.size::<Points>();
+    assert_eq!(595, width.pt.round() as i32);
+    assert_eq!(842, height.pt.round() as i32);
+    let (width, height) = PageSize::LETTER.size::<Points>();
+    assert_eq!((612.0, 792.0), (width.pt, height.pt));
+    assert_eq!(Orientation::Portrait, PageSize::A4.orientation());
+    assert_eq!(PageSize::A5.landscape(), PageSize::A5.flipped());
+    assert_eq!(PageSize::A5, PageSize::A5.landscape().portrait());
+    assert_eq!(PageSize::A5, PageSize::A5.portrait());
+    let ledger = PageSize::TABLOID.landscape();
+    assert_eq!(Orientation::Landscape, ledger.orientation());
+    assert_eq!(17.0 * 72.0, ledger.width::<Points>().pt);
+}
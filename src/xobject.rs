@@ -0,0 +1,12 @@
+/// A form XObject that is part of a document, i.e. reusable content created
+/// with [Pdf::create_form](struct.Pdf.html#method.create_form).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Form {
+    pub(crate) object_id: usize,
+}
+
+impl Form {
+    pub(crate) fn new(object_id: usize) -> Self {
+        Form { object_id }
+    }
+}